@@ -28,6 +28,12 @@ impl< N : RV, I : RV, const M : usize > RV for DArray<N, I, M> where
     type R = ArrayVec<I::R, M>;
 }
 
+// Schemas that always take exactly BYTES bytes, whatever their content; DropInterp can skip any of
+// them without looking at the bytes.
+pub trait FixedWidth {
+    const BYTES : usize;
+}
+
 macro_rules! number_parser {
     ($p:ident, $t:ty) => {
 
@@ -38,12 +44,115 @@ macro_rules! number_parser {
             type R = $t;
         }
 
+    };
+    ($p:ident, $t:ty, $size:expr) => {
+
+        number_parser! { $p, $t }
+
+        impl<const E: Endianness> FixedWidth for $p<E> {
+            const BYTES : usize = $size;
+        }
+
     }
 }
 
-number_parser! { U16, u16 }
-number_parser! { U32, u32 }
-number_parser! { U64, u64 }
+number_parser! { U16, u16, 2 }
+// Three bytes, zero-extended to a u32. Only big- and little-endian: middle-endian is defined in
+// terms of 16-bit words, which three bytes don't divide into.
+number_parser! { U24, u32 }
+impl FixedWidth for U24<{Endianness::Big}> {
+    const BYTES : usize = 3;
+}
+impl FixedWidth for U24<{Endianness::Little}> {
+    const BYTES : usize = 3;
+}
+number_parser! { U32, u32, 4 }
+number_parser! { U64, u64, 8 }
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum BitOrder {
+    MsbFirst,
+    LsbFirst,
+}
+
+// A fixed number of bytes read as individual flag bits; ORDER decides which bit of each byte is
+// numbered first.
+#[derive(Default)]
+pub struct BitField<const BYTES : usize, const ORDER : BitOrder>;
+
+impl<const BYTES : usize, const ORDER : BitOrder> RV for BitField<BYTES, ORDER> {
+    type R = crate::interp_parser::Bits<BYTES, ORDER>;
+}
+
+// What to do with the unused high nibble of the first byte when a BCD number has an odd number of
+// digits.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum BcdPadding {
+    Ignore,
+    Zero,
+}
+
+// Binary-coded decimal: DIGITS decimal digits packed two per byte, most significant first, in
+// ceil(DIGITS/2) bytes.
+#[derive(Default)]
+pub struct Bcd<const DIGITS : usize, const PAD : BcdPadding>;
+
+impl<const DIGITS : usize, const PAD : BcdPadding> RV for Bcd<DIGITS, PAD> {
+    type R = u64;
+}
+
+// Unsigned LEB128, the protobuf varint encoding: seven bits per byte, least significant group
+// first, with the high bit set on every byte but the last, decoded into T. With STRICT, encodings
+// longer than necessary, i.e. ending in a zero group after the first, are rejected, so that only
+// the canonical encoding of each value is accepted.
+#[derive(Default)]
+pub struct ULEB128<T, const STRICT : bool>(pub core::marker::PhantomData<T>);
+
+impl<T, const STRICT : bool> RV for ULEB128<T, STRICT> {
+    type R = T;
+}
+
+// A signed integer in protobuf's zigzag encoding, as in sint32 and sint64: a ULEB128 of the
+// corresponding unsigned type, mapping 0, 1, 2, 3, ... to 0, -1, 1, -2, .... STRICT is as for
+// ULEB128.
+#[derive(Default)]
+pub struct ZigZag<T, const STRICT : bool>(pub core::marker::PhantomData<T>);
+
+impl<T, const STRICT : bool> RV for ZigZag<T, STRICT> {
+    type R = T;
+}
+
+// Exactly GROUPS bytes, each contributing its low 7 bits, least significant group first, decoded
+// into T. Unlike ULEB128 the high bit does not mark continuation; with STRICT it must be clear,
+// otherwise it is ignored.
+#[derive(Default)]
+pub struct FixedGroups<T, const GROUPS : usize, const STRICT : bool>(pub core::marker::PhantomData<T>);
+
+impl<T, const GROUPS : usize, const STRICT : bool> RV for FixedGroups<T, GROUPS, STRICT> {
+    type R = T;
+}
+
+// An ASN.1 DER length: a single byte below 0x80, or 0x80 plus the count of big-endian length
+// bytes that follow. Only the minimal, definite-length encoding is accepted.
+#[derive(Default)]
+pub struct DerLength;
+
+impl RV for DerLength {
+    type R = usize;
+}
+
+// An ASN.1 DER tag byte and DerLength, followed by a value with schema I taking exactly that many
+// bytes.
+pub struct Tlv<I>(pub I);
+
+// Bytes up to and including the first DELIM, read into a buffer of at most N bytes. With KEEP the
+// delimiter is kept at the end of the result, and counts toward N; otherwise it is dropped.
+#[derive(Default)]
+pub struct Until<const DELIM : u8, const N : usize, const KEEP : bool>;
+
+impl<const DELIM : u8, const N : usize, const KEEP : bool> RV for Until<DELIM, N, KEEP> {
+    type R = ArrayVec<u8, N>;
+}
 
 //pub enum OutOfBand {
 //    Prompt('a mut dyn Fn() -> usize),
@@ -63,3 +172,17 @@ impl< I : RV, N : RV > RV for NOf<I, N> where
 pub struct LengthFallback<N, S>(pub N, pub S);
 
 pub struct Alt<A, B>(pub A, pub B);
+
+// A tag byte followed by one of the schemas in the tuple T, chosen by the tag's value.
+pub struct Dispatch<T>(pub T);
+
+// One of several fixed byte sequences, which are given by the interpretation; see OneOfTags.
+pub struct Tags;
+
+// A flags byte followed by some of the schemas in the tuple T, in order; which ones are present is
+// given by bits of the flags byte.
+pub struct Flagged<T>(pub T);
+
+// One of the schemas in the tuple T, chosen by something outside this part of the data, such as
+// an earlier header field.
+pub struct OneOf<T>(pub T);
@@ -1,7 +1,10 @@
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum Endianness {
     Big,
     Little,
+    // PDP-11 ordering: 16-bit words are little-endian, but multi-word values store the most
+    // significant word first.
+    Middle,
 }
 
 pub trait FixedSized {
@@ -37,3 +40,74 @@ macro_rules! impl_convert {
 impl_convert! { u16, 2 }
 impl_convert! { u32, 4 }
 impl_convert! { u64, 8 }
+impl_convert! { u8, 1 }
+
+impl Convert<{Endianness::Middle}> for u8 {
+    fn deserialize(bytes: Self::Array) -> Self {
+        u8::from_le_bytes(bytes)
+    }
+}
+
+impl Convert<{Endianness::Middle}> for u16 {
+    fn deserialize(bytes: Self::Array) -> Self {
+        u16::from_le_bytes(bytes)
+    }
+}
+
+impl Convert<{Endianness::Middle}> for u32 {
+    fn deserialize([b0, b1, b2, b3]: Self::Array) -> Self {
+        u32::from_le_bytes([b2, b3, b0, b1])
+    }
+}
+
+impl Convert<{Endianness::Middle}> for u64 {
+    fn deserialize([b0, b1, b2, b3, b4, b5, b6, b7]: Self::Array) -> Self {
+        u64::from_le_bytes([b6, b7, b4, b5, b2, b3, b0, b1])
+    }
+}
+
+// Three-byte values are read by padding them to four bytes in the same byte order and converting
+// those. There is no middle-endian impl, since middle-endian is defined in terms of 16-bit words.
+pub trait ZeroExtend<const E : Endianness> {
+    fn zero_extend(bytes: [u8; 3]) -> [u8; 4];
+}
+
+impl ZeroExtend<{Endianness::Big}> for u32 {
+    fn zero_extend([b0, b1, b2]: [u8; 3]) -> [u8; 4] {
+        [0, b0, b1, b2]
+    }
+}
+
+impl ZeroExtend<{Endianness::Little}> for u32 {
+    fn zero_extend([b0, b1, b2]: [u8; 3]) -> [u8; 4] {
+        [b0, b1, b2, 0]
+    }
+}
+
+impl FixedSized for bool {
+    type Array = [u8; 1];
+}
+
+// Any nonzero byte deserializes to true; formats that only admit 0 and 1 should use TryConvert
+// instead.
+impl<const E : Endianness> Convert<E> for bool {
+    fn deserialize(bytes: Self::Array) -> Self {
+        bytes[0] != 0
+    }
+}
+
+// Like Convert, for types where not every byte pattern is a valid value; None means the bytes
+// should be rejected.
+pub trait TryConvert<const E : Endianness>: FixedSized + Sized {
+    fn try_deserialize(bytes: Self::Array) -> Option<Self>;
+}
+
+impl<const E : Endianness> TryConvert<E> for bool {
+    fn try_deserialize(bytes: Self::Array) -> Option<Self> {
+        match bytes[0] {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
@@ -1,18 +1,36 @@
 use crate::core_parsers::*;
-use crate::endianness::{Endianness, Convert};
-use arrayvec::ArrayVec;
+use crate::endianness::{Endianness, Convert, ZeroExtend};
+use arrayvec::{ArrayVec, ArrayString};
 
 #[cfg(feature = "logging")]
-use ledger_log::error;
+use ledger_log::{error, trace};
 
+// Every variant other than Reject is a more specific reason for rejecting the input; callers that
+// don't care why should treat all of them as Reject. New reasons are added as parsers need to
+// report them, hence non_exhaustive: match with a wildcard arm.
 #[derive(PartialEq, Debug)]
+#[non_exhaustive]
 pub enum OOB {
     // Prompt removed due to excessive memory use; we gain testability improvements if we can
     // reinstate an OOB for prompts and do the co-routine again, but we can't do that at this
     // memory use.
     //
     // Prompt([ArrayString<128>;2]),
-    Reject
+    Reject,
+    // The same element appeared twice where each must be distinct; see DArrayUnique.
+    Duplicate,
+    // A length-prefixed value was shorter or longer than its prefix said, or the prefix was not the
+    // one required; see StrictLengthed and ExpectLength.
+    LengthMismatch,
+    // The element at this index of an Array or DArray was rejected; see parse_element. Only the innermost
+    // array reports its index: when the failing element is itself an array, its RejectedElement is
+    // passed through as is, and the index in the outer array is lost.
+    RejectedElement(usize),
+    // A DArray's length prefix is more than its capacity, so it was rejected before reading any
+    // elements; see DArrayTruncating for keeping a prefix instead.
+    TooManyElements,
+    // A length prefix doesn't fit in a usize, e.g. a U64 length on a 32-bit target.
+    LengthOverflow,
 }
 
 // PResult stands for Partial Result
@@ -30,6 +48,36 @@ pub type RemainingSlice<'a> = &'a [u8];
 // return the remaining slice for further elaboration or resuming.
 pub type ParseResult<'a> = Result<RemainingSlice<'a>, (PResult<OOB>, RemainingSlice<'a>)>;
 
+// The same information as ParseResult, spelled out, for driver loops and combinators where telling
+// "needs more input" from a rejection by whether the OOB is None is too easy to get wrong. Convert
+// with From/Into at the boundary; parsers themselves still return ParseResult.
+#[derive(PartialEq, Debug)]
+pub enum ParseStatus<'a> {
+    Done(RemainingSlice<'a>),
+    NeedMore(RemainingSlice<'a>),
+    Reject(OOB, RemainingSlice<'a>),
+}
+
+impl<'a> From<ParseResult<'a>> for ParseStatus<'a> {
+    fn from(result: ParseResult<'a>) -> Self {
+        match result {
+            Ok(rest) => ParseStatus::Done(rest),
+            Err((None, rest)) => ParseStatus::NeedMore(rest),
+            Err((Some(oob), rest)) => ParseStatus::Reject(oob, rest),
+        }
+    }
+}
+
+impl<'a> From<ParseStatus<'a>> for ParseResult<'a> {
+    fn from(status: ParseStatus<'a>) -> Self {
+        match status {
+            ParseStatus::Done(rest) => Ok(rest),
+            ParseStatus::NeedMore(rest) => Err((None, rest)),
+            ParseStatus::Reject(oob, rest) => Err((Some(oob), rest)),
+        }
+    }
+}
+
 pub fn reject<'a, R>(chunk: &'a [u8]) -> Result<R, (PResult<OOB>, &'a [u8])> {
     Err((Some(OOB::Reject), chunk))
 }
@@ -44,6 +92,8 @@ pub fn need_more<'a, R>(chunk: &'a [u8]) -> Result<R, (PResult<OOB>, &'a [u8])>
 pub trait ParserCommon<P> {
     type State;
     type Returning;
+    // How much RAM the state takes, including that of any subparsers, for budgeting stack use.
+    const STATE_SIZE : usize = core::mem::size_of::<Self::State>();
     fn init(&self) -> Self::State;
     fn init_in_place(&self, state: *mut core::mem::MaybeUninit<Self::State>) {
         unsafe { (*state).as_mut_ptr().write(self.init()); }
@@ -59,6 +109,36 @@ pub trait DynParser<P>: ParserCommon<P> {
     fn init_param(&self, params: Self::Parameter, state: &mut Self::State, destination: &mut Option<Self::Returning>);
 }
 
+// Run a parser to completion over a sequence of chunks, feeding the next chunk whenever the parser
+// asks for more. Running out of chunks before the parser accepts, or having input left over after
+// it accepts, is treated as a rejection.
+pub fn parse_all<'c, P, T: InterpParser<P>>(parser: &T, chunks: impl IntoIterator<Item = &'c [u8]>) -> Result<T::Returning, OOB> {
+    let mut chunk_iter = chunks.into_iter();
+    let mut state = parser.init();
+    let mut destination = None;
+    loop {
+        let chunk = chunk_iter.next().ok_or(OOB::Reject)?;
+        match parser.parse(&mut state, chunk, &mut destination) {
+            Ok(remaining) => {
+                if !remaining.is_empty() || chunk_iter.any(|c| !c.is_empty()) {
+                    return Err(OOB::Reject);
+                }
+                return destination.ok_or(OOB::Reject);
+            }
+            Err((None, _)) => { }
+            Err((Some(oob), _)) => { return Err(oob); }
+        }
+    }
+}
+
+// Log the state and result sizes of a parser, to check whether it will fit before flashing. The
+// state includes that of all its subparsers, so calling this for the top-level parser and then
+// for some of its parts shows where the memory goes.
+#[cfg(feature = "logging")]
+pub fn log_state_size<P, T: ParserCommon<P>>(name: &str) {
+    trace!("{} state size: {}, result size: {}", name, T::STATE_SIZE, core::mem::size_of::<Option<T::Returning>>());
+}
+
 pub struct DefaultInterp;
 
 pub struct SubInterp<S>(pub S);
@@ -129,6 +209,13 @@ pub struct ForwardArrayParserState<Item, SubparserState, const N : usize > {
     subparser_state: SubparserState
 }
 
+impl<Item, SubparserState, const N : usize> ForwardArrayParserState<Item, SubparserState, N> {
+    // How many elements have been parsed so far, for showing progress through a long array.
+    pub fn progress(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
 
 /* Note: we use this for parsing numbers. Additional requirement: don't stream into destination,
  * because number parser will be recreating destination each time. */
@@ -147,13 +234,9 @@ impl<I, S : InterpParser<I>, const N : usize> InterpParser<Array< I, N>> for Sub
     fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
         let mut remaining : &'a [u8] = chunk;
         while !state.buffer.is_full() {
-            match self.0.parse(&mut state.subparser_state, remaining, &mut state.subparser_destination)? {
-                new_chunk => {
-                    remaining = new_chunk;
-                    state.buffer.push(core::mem::take(&mut state.subparser_destination).ok_or((Some(OOB::Reject), remaining))?);
-                    state.subparser_state = <S as ParserCommon<I>>::init(&self.0);
-                }
-            }
+            remaining = parse_element(&self.0, state.buffer.len(), &mut state.subparser_state, remaining, &mut state.subparser_destination)?;
+            state.buffer.push(core::mem::take(&mut state.subparser_destination).ok_or((Some(OOB::Reject), remaining))?);
+            state.subparser_state = <S as ParserCommon<I>>::init(&self.0);
         }
         match state.buffer.take().into_inner() {
             Ok(rv) => {
@@ -183,293 +266,1437 @@ macro_rules! number_parser {
                 Ok(remainder)
             }
         }
-        impl<const E: Endianness> ParserCommon<$p<E>> for DropInterp {
-            type State = <SubInterp<DropInterp> as ParserCommon<Array<Byte, $size>>>::State;
-            type Returning = ();
-            fn init(&self) -> Self::State {
-                <SubInterp<DropInterp> as ParserCommon<Array<Byte, $size>>>::init(&SubInterp(DropInterp))
-            }
-        }
-        impl<const E: Endianness> InterpParser<$p<E>> for DropInterp {
-            #[inline(never)]
-            fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
-                let mut sub_destination : Option<[(); $size]> = None;
-                let remainder = <SubInterp<DropInterp> as InterpParser<Array<Byte, $size>>>::parse(&SubInterp(DropInterp), state, chunk, &mut sub_destination)?;
-                *destination = Some(());
-                return Ok(remainder);
-            }
-        }
     }
 }
 number_parser! { U16, 2 }
 number_parser! { U32, 4 }
 number_parser! { U64, 8 }
 
-pub enum ForwardDArrayParserState<N, IS, I, const M : usize > {
-    Length(N),
-    Elements(ArrayVec<I, M>, usize, IS, Option<I>),
-    Done
-}
-
-use core::convert::TryFrom;
-impl<N, I, S : ParserCommon<I>, const M : usize> ParserCommon<DArray<N, I, M> > for SubInterp<S> where
-    DefaultInterp : ParserCommon<N>,
-    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>,
-    <S as ParserCommon<I>>::Returning: Clone{
-    type State=ForwardDArrayParserState<<DefaultInterp as ParserCommon<N>>::State, <S as ParserCommon<I>>::State, <S as ParserCommon<I>>::Returning, M>;
-    type Returning = ArrayVec<<S as ParserCommon<I>>::Returning, M>;
+impl<const E: Endianness> ParserCommon<U24<E>> for DefaultInterp where u32 : ZeroExtend<E> + Convert<E> {
+    type State = <DefaultInterp as ParserCommon<Array<Byte, 3>>>::State;
+    type Returning = <U24<E> as RV>::R;
     fn init(&self) -> Self::State {
-        Self::State::Length(<DefaultInterp as ParserCommon<N>>::init(&DefaultInterp))
+        <DefaultInterp as ParserCommon<Array<Byte, 3>>>::init(&DefaultInterp)
     }
 }
-
-impl<N, I, S : InterpParser<I>, const M : usize> InterpParser<DArray<N, I, M> > for SubInterp<S> where
-    DefaultInterp : InterpParser<N>,
-    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>,
-    <S as ParserCommon<I>>::Returning: Clone{
+impl<const E: Endianness> InterpParser<U24<E>> for DefaultInterp where u32 : ZeroExtend<E> + Convert<E> {
     #[inline(never)]
     fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
-        use ForwardDArrayParserState::*;
-        let mut cursor : &'a [u8] = chunk;
-        loop {
-            match state {
-                Length(ref mut nstate) => {
-                    let mut sub_destination : Option<<DefaultInterp as ParserCommon<N>>::Returning> = None;
-                    let newcur : &'a [u8] = <DefaultInterp as InterpParser<N>>::parse(&DefaultInterp, nstate, chunk, &mut sub_destination)?;
-                    let len_temp = sub_destination.ok_or((Some(OOB::Reject), newcur))?;
-                    cursor = newcur;
-                    let len = <usize as TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>>::try_from(len_temp).or(Err((Some(OOB::Reject), newcur)))?;
-                    set_from_thunk(state, || Elements(ArrayVec::new(), len, <S as ParserCommon<I>>::init(&self.0), None));
-                }
-                Elements(ref mut vec, len, ref mut istate, ref mut sub_destination) => {
-                    while vec.len() < *len {
-                        cursor = self.0.parse(istate, cursor, sub_destination)?;
-                        vec.try_push(core::mem::take(sub_destination).ok_or((Some(OOB::Reject), cursor))?).or(Err((Some(OOB::Reject), cursor)))?;
-                        *istate = <S as ParserCommon<I>>::init(&self.0);
-                    }
-                    *destination = match core::mem::replace(state, Done) { Elements(vec, _, _, _) => Some(vec), _ => break Err((Some(OOB::Reject), cursor)), };
-                    break Ok(cursor);
-                }
-                Done => { break Err((Some(OOB::Reject), cursor)); }
-            }
-        }
+        let mut sub_destination : Option<[u8; 3]> = None;
+        let remainder = <DefaultInterp as InterpParser<Array<Byte, 3>>>::parse(&DefaultInterp, state, chunk, &mut sub_destination)?;
+        let bytes = <u32 as ZeroExtend<E>>::zero_extend(sub_destination.ok_or((Some(OOB::Reject), remainder))?);
+        *destination = Some(<u32 as Convert<E>>::deserialize(bytes));
+        Ok(remainder)
     }
 }
 
-
-impl< I, const N : usize >  ParserCommon<Array<I, N>> for DefaultInterp where
-    DefaultInterp : ParserCommon<I> {
-    type State = <SubInterp<DefaultInterp> as ParserCommon<Array< I, N> >>::State;
-    type Returning = <SubInterp<DefaultInterp> as ParserCommon<Array< I, N> >>::Returning;
+// Skipping a fixed-width schema only needs a count of the bytes skipped so far.
+impl<P : FixedWidth> ParserCommon<P> for DropInterp {
+    type State = usize;
+    type Returning = ();
     fn init(&self) -> Self::State {
-        <SubInterp<DefaultInterp> as ParserCommon<Array<I, N>>>::init(&SubInterp(DefaultInterp))
+        0
     }
 }
-
-impl< I, const N : usize >  InterpParser<Array<I, N>> for DefaultInterp where
-    DefaultInterp : InterpParser<I> {
+impl<P : FixedWidth> InterpParser<P> for DropInterp {
     #[inline(never)]
     fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
-        <SubInterp<DefaultInterp> as InterpParser<Array<I, N>>>::parse(&SubInterp(DefaultInterp), state, chunk, destination)
+        let take = core::cmp::min(chunk.len(), P::BYTES - *state);
+        *state += take;
+        if *state < P::BYTES {
+            return Err((None, &chunk[take..]));
+        }
+        *destination = Some(());
+        Ok(&chunk[take..])
     }
 }
 
+// Number parsers whose byte order is chosen at runtime rather than by a const parameter, e.g. from
+// a flag in a header read earlier with DynBind. The endianness given at construction is the
+// default, and init_param overrides it.
+macro_rules! dyn_endian_number_parser {
+    ($p:ident, $t:ty, $size:expr) => {
+        #[derive(Clone)]
+        pub struct $p(pub Endianness);
 
-/* // TODO: determine why this doesn't work.
-impl< N, I, const M : usize> InterpParser<DArray<N, I, M>> for DefaultInterp where
-    DefaultInterp : InterpParser<I> + InterpParser<N>, 
-    usize: From<<DefaultInterp as InterpParser<N>>::Returning> {
-    type State = <SubInterp<DefaultInterp> as InterpParser<DArray< N, I, M> > >::State;
-    type Returning = <SubInterp<DefaultInterp> as InterpParser<DArray< N, I, M> > >::Returning;
-    fn init(&self) -> Self::State {
-        <SubInterp<DefaultInterp> as InterpParser<DArray<N, I, M>>>::init(&SubInterp(DefaultInterp))
-    }
-    #[inline(never)]
-    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8]) -> ParseResult<'a> {
-        <SubInterp<DefaultInterp> as InterpParser<DArray<N, I, M>>>::parse(&SubInterp(DefaultInterp), state, chunk)
+        impl ParserCommon<Array<Byte, $size>> for $p {
+            type State = (Endianness, <DefaultInterp as ParserCommon<Array<Byte, $size>>>::State);
+            type Returning = $t;
+            fn init(&self) -> Self::State {
+                (self.0, <DefaultInterp as ParserCommon<Array<Byte, $size>>>::init(&DefaultInterp))
+            }
+        }
+        impl InterpParser<Array<Byte, $size>> for $p {
+            #[inline(never)]
+            fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+                let mut sub_destination : Option<[u8; $size]> = None;
+                let remainder = <DefaultInterp as InterpParser<Array<Byte, $size>>>::parse(&DefaultInterp, &mut state.1, chunk, &mut sub_destination)?;
+                let bytes = sub_destination.ok_or((Some(OOB::Reject), remainder))?;
+                *destination = Some(match state.0 {
+                    Endianness::Big => Convert::<{Endianness::Big}>::deserialize(bytes),
+                    Endianness::Little => Convert::<{Endianness::Little}>::deserialize(bytes),
+                    Endianness::Middle => Convert::<{Endianness::Middle}>::deserialize(bytes),
+                });
+                Ok(remainder)
+            }
+        }
+        impl DynParser<Array<Byte, $size>> for $p {
+            type Parameter = Endianness;
+            #[inline(never)]
+            fn init_param(&self, param: Self::Parameter, state: &mut Self::State, _destination: &mut Option<Self::Returning>) {
+                set_from_thunk(state, || (param, <DefaultInterp as ParserCommon<Array<Byte, $size>>>::init(&DefaultInterp)));
+            }
+        }
     }
 }
-*/
+dyn_endian_number_parser! { DynEndianU16, u16, 2 }
+dyn_endian_number_parser! { DynEndianU32, u32, 4 }
+dyn_endian_number_parser! { DynEndianU64, u64, 8 }
 
-// Action is essentailly an fmap that can fail.
-// We _could_ constraint F to actually be an fn(..) -> Option<()> to improve error messages when
-// functions do not have the correct shape, but that reduces our ability to write different
-// instances later.
-#[derive(Clone)]
-pub struct Action<S, F>(pub S, pub F);
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bits<const BYTES : usize, const ORDER : BitOrder>(pub [u8; BYTES]);
 
-impl<A, R, S : ParserCommon<A>> ParserCommon<A> for Action<S, fn(&<S as ParserCommon<A>>::Returning, &mut Option<R>) -> Option<()>>
-{
-    type State = (<S as ParserCommon<A> >::State, Option<<S as ParserCommon<A> >::Returning>);
-    type Returning = R;
+impl<const BYTES : usize, const ORDER : BitOrder> Bits<BYTES, ORDER> {
+    // With MsbFirst, bit 0 is the high bit of the first byte; with LsbFirst it is the low bit.
+    // Panics if bit is past the end of the field, like slice indexing.
+    pub fn get(&self, bit: usize) -> bool {
+        let byte = self.0[bit / 8];
+        match ORDER {
+            BitOrder::MsbFirst => byte & (0x80 >> (bit % 8)) != 0,
+            BitOrder::LsbFirst => byte & (0x01 << (bit % 8)) != 0,
+        }
+    }
 
-    // #[inline(never)] // Causes stack size increase
-    fn init(&self) -> Self::State {
-        (<S as ParserCommon<A>>::init(&self.0), None)
+    // Read len consecutive bits starting at start as an unsigned integer. The bit numbered first is
+    // the most significant for MsbFirst and the least significant for LsbFirst, so ranges read the
+    // way the format's documentation draws them in either case. Panics if len is more than 64, as
+    // the result wouldn't fit, or if the range is past the end of the field.
+    pub fn get_range(&self, start: usize, len: usize) -> u64 {
+        assert!(len <= 64, "Bits::get_range: more than 64 bits");
+        (start..start + len).enumerate().fold(0, |acc, (i, bit)| {
+            let b = self.get(bit) as u64;
+            match ORDER {
+                BitOrder::MsbFirst => (acc << 1) | b,
+                BitOrder::LsbFirst => acc | (b << i),
+            }
+        })
     }
+}
 
-    #[inline(never)]
-    fn init_in_place(&self, state: *mut core::mem::MaybeUninit<Self::State>) {
-       self.0.init_in_place(unsafe { core::ptr::addr_of_mut!((*(*state).as_mut_ptr()).0) as *mut core::mem::MaybeUninit<<S as ParserCommon<A> >::State> });
-       call_fn( || unsafe { (core::ptr::addr_of_mut!((*(*state).as_mut_ptr()).1) as *mut Option<<S as ParserCommon<A> >::Returning> ).write(None)} );
+impl<const BYTES : usize, const ORDER : BitOrder> ParserCommon<BitField<BYTES, ORDER>> for DefaultInterp {
+    type State = <DefaultInterp as ParserCommon<Array<Byte, BYTES>>>::State;
+    type Returning = <BitField<BYTES, ORDER> as RV>::R;
+    fn init(&self) -> Self::State {
+        <DefaultInterp as ParserCommon<Array<Byte, BYTES>>>::init(&DefaultInterp)
     }
 }
 
-impl<A, R, S : InterpParser<A>> InterpParser<A> for Action<S, fn(&<S as ParserCommon<A>>::Returning, &mut Option<R>) -> Option<()>>
-{
+impl<const BYTES : usize, const ORDER : BitOrder> InterpParser<BitField<BYTES, ORDER>> for DefaultInterp {
     #[inline(never)]
     fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
-        let new_chunk = self.0.parse(&mut state.0, chunk, &mut state.1)?;
-        match (self.1)(state.1.as_ref().ok_or((Some(OOB::Reject),new_chunk))?, destination) {
-            None => { Err((Some(OOB::Reject),new_chunk)) }
-            Some(()) => { Ok(new_chunk) }
-        }
+        let mut sub_destination : Option<[u8; BYTES]> = None;
+        let remainder = <DefaultInterp as InterpParser<Array<Byte, BYTES>>>::parse(&DefaultInterp, state, chunk, &mut sub_destination)?;
+        *destination = Some(Bits(sub_destination.ok_or((Some(OOB::Reject), remainder))?));
+        Ok(remainder)
     }
 }
 
-impl<A, R, S : DynParser<A>> DynParser<A> for Action<S, fn(&<S as ParserCommon<A>>::Returning, &mut Option<R>) -> Option<()>>
-    {
-        type Parameter = S::Parameter;
-        #[inline(never)]
-        fn init_param(&self, param: Self::Parameter, state: &mut Self::State, _destination: &mut Option<Self::Returning>) {
-            set_from_thunk(&mut state.0, || <S as ParserCommon<A>>::init(&self.0));
-            set_from_thunk(&mut state.1, || None);
-            self.0.init_param(param, &mut state.0, &mut state.1);
-        }
-    }
+// An unsigned integer too wide for the primitive types, kept as its big-endian bytes. Comparison
+// is numeric, since that agrees with comparing the bytes in order, and Display and LowerHex print
+// it without leading zeros; there is no arithmetic, which is all we need for showing e.g. 256-bit
+// amounts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BigUintBytes<const N : usize>(pub [u8; N]);
 
-/* This impl exists to allow the _function_ of an Action to be the target of the parameter for
- * DynParser, thus giving an escape hatch to thread a parameter past a non-parameterized
- * parser. Whether this should still be an Action as opposed to some other name is not immediately
- * clear. */
-impl<A, R, S : ParserCommon<A>, C> ParserCommon<A> for Action<S, fn(&<S as
-    ParserCommon<A>>::Returning, &mut Option<R>, C) -> Option<()>>
-{
-    type State = (<S as ParserCommon<A> >::State, Option<<S as ParserCommon<A> >::Returning>, Option<C>);
-    type Returning = R;
+impl<const N : usize> BigUintBytes<N> {
+    pub fn is_zero(&self) -> bool {
+        self.0.iter().all(|b| *b == 0)
+    }
 
-    #[inline(never)]
-    fn init(&self) -> Self::State {
-        (<S as ParserCommon<A>>::init(&self.0), None, None)
+    // Long division by a divisor below 2^32, returning the quotient and remainder. The running
+    // remainder stays below 2^38, so this is all u64 arithmetic, which 32-bit targets do in
+    // software far more cheaply than u128.
+    fn div_rem(&self, divisor: u32) -> (Self, u32) {
+        let mut quotient = [0; N];
+        let mut rem : u64 = 0;
+        for (q, b) in quotient.iter_mut().zip(self.0.iter()) {
+            let cur = (rem << 8) | *b as u64;
+            *q = (cur / divisor as u64) as u8;
+            rem = cur % divisor as u64;
+        }
+        (BigUintBytes(quotient), rem as u32)
     }
 
-    #[inline(never)]
-    fn init_in_place(&self, state: *mut core::mem::MaybeUninit<Self::State>) {
-       self.0.init_in_place(unsafe { core::ptr::addr_of_mut!((*(*state).as_mut_ptr()).0) as *mut core::mem::MaybeUninit<<S as ParserCommon<A> >::State> });
-       call_fn( || unsafe { (core::ptr::addr_of_mut!((*(*state).as_mut_ptr()).1) as *mut Option<<S as ParserCommon<A> >::Returning> ).write(None)} );
-       call_fn( || unsafe { (core::ptr::addr_of_mut!((*(*state).as_mut_ptr()).2) as *mut Option<C> ).write(None)} );
+    // Writes nine decimal digits at a time, most significant group first. Recursing once per group
+    // rather than per digit keeps the stack shallow: nine frames for 32 bytes.
+    fn fmt_decimal(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        const GROUP : u32 = 1_000_000_000;
+        let (quotient, rem) = self.div_rem(GROUP);
+        if quotient.is_zero() {
+            write!(f, "{}", rem)
+        } else {
+            quotient.fmt_decimal(f)?;
+            write!(f, "{:09}", rem)
+        }
     }
 }
 
-impl<A, R, S : InterpParser<A>, C> InterpParser<A> for Action<S, fn(&<S as ParserCommon<A>>::Returning, &mut Option<R>, C) -> Option<()>>
-{
-    #[inline(never)]
-    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
-        let new_chunk = self.0.parse(&mut state.0, chunk, &mut state.1)?;
-        match (self.1)(state.1.as_ref().ok_or((Some(OOB::Reject),new_chunk))?, destination, core::mem::take(&mut state.2).ok_or((Some(OOB::Reject),new_chunk))?) {
-            None => { Err((Some(OOB::Reject),new_chunk)) }
-            Some(()) => { Ok(new_chunk) }
-        }
+impl<const N : usize> core::fmt::Display for BigUintBytes<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.fmt_decimal(f)
     }
 }
 
-impl<A, R, S : ParserCommon<A>, C> DynParser<A> for Action<S, fn(&<S as ParserCommon<A>>::Returning, &mut Option<R>, C) -> Option<()>>
-    {
-        type Parameter = C;
-        #[inline(never)]
-        fn init_param(&self, param: Self::Parameter, state: &mut Self::State, _destination: &mut Option<Self::Returning>) {
-            set_from_thunk(&mut state.0, || <S as ParserCommon<A>>::init(&self.0));
-            set_from_thunk(&mut state.1, || None);
-            set_from_thunk(&mut state.2, || Some(param));
+impl<const N : usize> core::fmt::LowerHex for BigUintBytes<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut digits = self.0.iter().skip_while(|b| **b == 0);
+        match digits.next() {
+            None => write!(f, "0"),
+            Some(first) => {
+                write!(f, "{:x}", first)?;
+                digits.try_for_each(|b| write!(f, "{:02x}", b))
+            }
         }
     }
+}
 
-/* A MoveAction is the same as an Action with the distinction that it takes it's argument via Move,
- * thus enabling it to work with types that do not have Copy or Clone and have nontrivial semantics
- * involving Drop. */
-pub struct MoveAction<S, F>(pub S, pub F);
-impl<A, R, S : ParserCommon<A>> ParserCommon<A> for MoveAction<S, fn(<S as ParserCommon<A>>::Returning, &mut Option<R>) -> Option<()>>
-{
-    type State = (<S as ParserCommon<A> >::State, Option<<S as ParserCommon<A> >::Returning>);
-    type Returning = R;
+// Reads an Array<Byte, N> as a BigUintBytes<N>.
+pub struct BigUintInterp;
 
-    #[inline(never)]
+impl<const N : usize> ParserCommon<Array<Byte, N>> for BigUintInterp {
+    type State = <DefaultInterp as ParserCommon<Array<Byte, N>>>::State;
+    type Returning = BigUintBytes<N>;
     fn init(&self) -> Self::State {
-        (<S as ParserCommon<A>>::init(&self.0), None)
-    }
-
-    #[inline(never)]
-    fn init_in_place(&self, state: *mut core::mem::MaybeUninit<Self::State>) {
-       self.0.init_in_place(unsafe { core::ptr::addr_of_mut!((*(*state).as_mut_ptr()).0) as *mut core::mem::MaybeUninit<<S as ParserCommon<A> >::State> });
-       call_fn( || unsafe { (core::ptr::addr_of_mut!((*(*state).as_mut_ptr()).1) as *mut Option<<S as ParserCommon<A> >::Returning> ).write(None)} );
+        <DefaultInterp as ParserCommon<Array<Byte, N>>>::init(&DefaultInterp)
     }
 }
 
-impl<A, R, S : InterpParser<A>> InterpParser<A> for MoveAction<S, fn(<S as ParserCommon<A>>::Returning, &mut Option<R>) -> Option<()>>
-{
+impl<const N : usize> InterpParser<Array<Byte, N>> for BigUintInterp {
     #[inline(never)]
     fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
-        let new_chunk = self.0.parse(&mut state.0, chunk, &mut state.1)?;
-        match (self.1)(core::mem::take(&mut state.1).ok_or((Some(OOB::Reject),new_chunk))?, destination) {
-            None => { Err((Some(OOB::Reject),new_chunk)) }
-            Some(()) => { Ok(new_chunk) }
-        }
+        let mut sub_destination : Option<[u8; N]> = None;
+        let remainder = <DefaultInterp as InterpParser<Array<Byte, N>>>::parse(&DefaultInterp, state, chunk, &mut sub_destination)?;
+        *destination = Some(BigUintBytes(sub_destination.ok_or((Some(OOB::Reject), remainder))?));
+        Ok(remainder)
     }
 }
 
-impl<A, R, S : DynParser<A>> DynParser<A> for MoveAction<S, fn(<S as ParserCommon<A>>::Returning, &mut Option<R>) -> Option<()>>
-    {
-        type Parameter = S::Parameter;
-        #[inline(never)]
-        fn init_param(&self, param: Self::Parameter, state: &mut Self::State, _destination: &mut Option<Self::Returning>) {
-            set_from_thunk(&mut state.0, || <S as ParserCommon<A>>::init(&self.0));
-            set_from_thunk(&mut state.1, || None);
-            self.0.init_param(param, &mut state.0, &mut state.1);
-        }
-    }
-
-fn rej<'a>(cnk: &'a [u8]) -> (PResult<OOB>, RemainingSlice<'a>) {
-    (Some(OOB::Reject), cnk)
+pub struct BcdState {
+    bytes_seen : usize,
+    value : u64,
 }
 
-pub struct Preaction<S>(pub fn() -> Option<()>, pub S);
-
-impl<A, S: ParserCommon<A>> ParserCommon<A> for Preaction<S> {
-    type State = Option<<S as ParserCommon<A>>::State>;
-    type Returning = <S as ParserCommon<A>>::Returning;
-
-    fn init(&self) -> Self::State { None }
+impl<const DIGITS : usize, const PAD : BcdPadding> ParserCommon<Bcd<DIGITS, PAD>> for DefaultInterp {
+    type State = BcdState;
+    type Returning = u64;
+    fn init(&self) -> Self::State {
+        BcdState { bytes_seen: 0, value: 0 }
+    }
 }
 
-impl<A, S: InterpParser<A>> InterpParser<A> for Preaction<S> {
+impl<const DIGITS : usize, const PAD : BcdPadding> InterpParser<Bcd<DIGITS, PAD>> for DefaultInterp {
     #[inline(never)]
     fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
-        loop { break match state {
-            None => {
-                (self.0)().ok_or((Some(OOB::Reject), chunk))?;
-                set_from_thunk(state, || Some(<S as ParserCommon<A>>::init(&self.1)));
-                continue;
+        let push_digit = |value: u64, digit: u8| -> Option<u64> {
+            if digit > 9 {
+                return None;
             }
-            Some(ref mut s) => <S as InterpParser<A>>::parse(&self.1, s, chunk, destination)
-        }}
+            value.checked_mul(10)?.checked_add(digit as u64)
+        };
+        let mut cursor = chunk;
+        while state.bytes_seen < DIGITS.div_ceil(2) {
+            let (byte, rest) = cursor.split_first().ok_or((None, cursor))?;
+            let (high, low) = (byte >> 4, byte & 0x0f);
+            if state.bytes_seen == 0 && DIGITS % 2 == 1 {
+                if PAD == BcdPadding::Zero && high != 0 {
+                    return Err(rej(cursor));
+                }
+            } else {
+                state.value = push_digit(state.value, high).ok_or(rej(cursor))?;
+            }
+            state.value = push_digit(state.value, low).ok_or(rej(cursor))?;
+            state.bytes_seen += 1;
+            cursor = rest;
+        }
+        *destination = Some(state.value);
+        Ok(cursor)
     }
 }
 
-#[derive(Clone)]
-// S is the first subparser to run
-// F is the continuation parser to run, which can depend on the result of S
-pub struct Bind<S, F>(pub S, pub F);
+pub struct ULEB128State<T> {
+    shift : u32,
+    value : T,
+}
 
-// Initially the state is the state of the first subparser, and its result location
-// After the first subparser runs, if it failed, then the whole bind parser will fail
-// but if it succeeds, then the parser state transitions to BindSecond.
-#[derive(InPlaceInit)]
-pub enum BindState<A,B,S:ParserCommon<A>,T:ParserCommon<B>> {
-    BindFirst(S::State, Option<<S as ParserCommon<A> >::Returning>),
-    BindSecond(T, <T as ParserCommon<B>>::State)
+macro_rules! uleb128_parser {
+    ($t:ty) => {
+        impl<const STRICT : bool> ParserCommon<ULEB128<$t, STRICT>> for DefaultInterp {
+            type State = ULEB128State<$t>;
+            type Returning = $t;
+            fn init(&self) -> Self::State {
+                ULEB128State { shift: 0, value: 0 }
+            }
+        }
+        impl<const STRICT : bool> InterpParser<ULEB128<$t, STRICT>> for DefaultInterp {
+            #[inline(never)]
+            fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+                let mut cursor = chunk;
+                loop {
+                    let (byte, rest) = cursor.split_first().ok_or((None, cursor))?;
+                    let group = (byte & 0x7f) as $t;
+                    // Reject groups that would shift set bits off the top of the value.
+                    state.value |= group.checked_shl(state.shift).filter(|g| g >> state.shift == group).ok_or(rej(cursor))?;
+                    if byte & 0x80 == 0 {
+                        if STRICT && *byte == 0 && state.shift > 0 {
+                            return Err(rej(cursor));
+                        }
+                        *destination = Some(state.value);
+                        return Ok(rest);
+                    }
+                    state.shift += 7;
+                    cursor = rest;
+                }
+            }
+        }
+        impl<const STRICT : bool> ParserCommon<ULEB128<$t, STRICT>> for DropInterp {
+            type State = <DefaultInterp as ParserCommon<ULEB128<$t, STRICT>>>::State;
+            type Returning = ();
+            fn init(&self) -> Self::State {
+                <DefaultInterp as ParserCommon<ULEB128<$t, STRICT>>>::init(&DefaultInterp)
+            }
+        }
+        impl<const STRICT : bool> InterpParser<ULEB128<$t, STRICT>> for DropInterp {
+            #[inline(never)]
+            fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+                let remainder = <DefaultInterp as InterpParser<ULEB128<$t, STRICT>>>::parse(&DefaultInterp, state, chunk, &mut None)?;
+                *destination = Some(());
+                Ok(remainder)
+            }
+        }
+    }
 }
+uleb128_parser! { u16 }
+uleb128_parser! { u32 }
+uleb128_parser! { u64 }
+uleb128_parser! { u128 }
 
-impl<A, B, S : ParserCommon<A>, T : ParserCommon<B>> ParserCommon<(A,B)> for Bind<S, fn(&<S as ParserCommon<A>>::Returning) -> Option<T>>
+macro_rules! zigzag_parser {
+    ($t:ty, $u:ty) => {
+        impl<const STRICT : bool> ParserCommon<ZigZag<$t, STRICT>> for DefaultInterp {
+            type State = ULEB128State<$u>;
+            type Returning = $t;
+            fn init(&self) -> Self::State {
+                <DefaultInterp as ParserCommon<ULEB128<$u, STRICT>>>::init(&DefaultInterp)
+            }
+        }
+        impl<const STRICT : bool> InterpParser<ZigZag<$t, STRICT>> for DefaultInterp {
+            #[inline(never)]
+            fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+                let mut raw = None;
+                let remainder = <DefaultInterp as InterpParser<ULEB128<$u, STRICT>>>::parse(&DefaultInterp, state, chunk, &mut raw)?;
+                let n = raw.ok_or(rej(remainder))?;
+                *destination = Some((n >> 1) as $t ^ -((n & 1) as $t));
+                Ok(remainder)
+            }
+        }
+        impl<const STRICT : bool> ParserCommon<ZigZag<$t, STRICT>> for DropInterp {
+            type State = ULEB128State<$u>;
+            type Returning = ();
+            fn init(&self) -> Self::State {
+                <DefaultInterp as ParserCommon<ULEB128<$u, STRICT>>>::init(&DefaultInterp)
+            }
+        }
+        impl<const STRICT : bool> InterpParser<ZigZag<$t, STRICT>> for DropInterp {
+            #[inline(never)]
+            fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+                <DropInterp as InterpParser<ULEB128<$u, STRICT>>>::parse(&DropInterp, state, chunk, destination)
+            }
+        }
+    }
+}
+zigzag_parser! { i32, u32 }
+zigzag_parser! { i64, u64 }
+
+// FixedGroups shares ULEB128's state; the shift also tells us how many groups we have seen.
+macro_rules! fixed_groups_parser {
+    ($t:ty) => {
+        impl<const GROUPS : usize, const STRICT : bool> ParserCommon<FixedGroups<$t, GROUPS, STRICT>> for DefaultInterp {
+            type State = ULEB128State<$t>;
+            type Returning = $t;
+            fn init(&self) -> Self::State {
+                ULEB128State { shift: 0, value: 0 }
+            }
+        }
+        impl<const GROUPS : usize, const STRICT : bool> InterpParser<FixedGroups<$t, GROUPS, STRICT>> for DefaultInterp {
+            #[inline(never)]
+            fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+                let mut cursor = chunk;
+                while (state.shift / 7) as usize != GROUPS {
+                    let (byte, rest) = cursor.split_first().ok_or((None, cursor))?;
+                    if STRICT && byte & 0x80 != 0 {
+                        return Err(rej(cursor));
+                    }
+                    let group = (byte & 0x7f) as $t;
+                    if group != 0 {
+                        state.value |= group.checked_shl(state.shift).filter(|g| g >> state.shift == group).ok_or(rej(cursor))?;
+                    }
+                    state.shift += 7;
+                    cursor = rest;
+                }
+                *destination = Some(state.value);
+                Ok(cursor)
+            }
+        }
+        impl<const GROUPS : usize, const STRICT : bool> ParserCommon<FixedGroups<$t, GROUPS, STRICT>> for DropInterp {
+            type State = <DefaultInterp as ParserCommon<FixedGroups<$t, GROUPS, STRICT>>>::State;
+            type Returning = ();
+            fn init(&self) -> Self::State {
+                <DefaultInterp as ParserCommon<FixedGroups<$t, GROUPS, STRICT>>>::init(&DefaultInterp)
+            }
+        }
+        impl<const GROUPS : usize, const STRICT : bool> InterpParser<FixedGroups<$t, GROUPS, STRICT>> for DropInterp {
+            #[inline(never)]
+            fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+                let remainder = <DefaultInterp as InterpParser<FixedGroups<$t, GROUPS, STRICT>>>::parse(&DefaultInterp, state, chunk, &mut None)?;
+                *destination = Some(());
+                Ok(remainder)
+            }
+        }
+    }
+}
+fixed_groups_parser! { u16 }
+fixed_groups_parser! { u32 }
+fixed_groups_parser! { u64 }
+fixed_groups_parser! { u128 }
+
+// The buffer is built in the destination, so there is no state of our own.
+impl<const DELIM : u8, const N : usize, const KEEP : bool> ParserCommon<Until<DELIM, N, KEEP>> for DefaultInterp {
+    type State = ();
+    type Returning = ArrayVec<u8, N>;
+    fn init(&self) -> Self::State { () }
+}
+
+impl<const DELIM : u8, const N : usize, const KEEP : bool> InterpParser<Until<DELIM, N, KEEP>> for DefaultInterp {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, _state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        let buffer = destination.get_or_insert_with(ArrayVec::new);
+        let mut cursor = chunk;
+        loop {
+            let (byte, rest) = cursor.split_first().ok_or((None, cursor))?;
+            if *byte != DELIM || KEEP {
+                // Reject as soon as the buffer is full rather than after reading the delimiter.
+                buffer.try_push(*byte).or(Err(rej(cursor)))?;
+            }
+            cursor = rest;
+            if *byte == DELIM {
+                return Ok(cursor);
+            }
+        }
+    }
+}
+
+// Interprets a length-prefixed byte string of at most MAX bytes as UTF-8 for display on a screen
+// that fits SHOW bytes. If the string is longer than that, it is cut at a character boundary and
+// an ellipsis is appended, all within SHOW bytes. The whole string is checked, including the part
+// that isn't shown, and invalid UTF-8 is rejected. Truncation is per character, not per grapheme,
+// so a combining mark can be separated from its base character.
+#[derive(Clone)]
+pub struct DisplayString<const MAX : usize, const SHOW : usize>;
+
+impl<const MAX : usize, const SHOW : usize> DisplayString<MAX, SHOW> {
+    // Used in init, so that a SHOW with no room for the ellipsis fails to compile.
+    const ROOM_FOR_ELLIPSIS : () = assert!(SHOW >= ELLIPSIS.len(), "DisplayString's SHOW must leave room for the ellipsis");
+}
+
+pub enum DisplayStringState<N, NR, const SHOW : usize> {
+    Length(N, Option<NR>),
+    Body {
+        remaining : usize,
+        // The bytes of a character that has been started but not finished.
+        pending : ArrayVec<u8, 4>,
+        // How many bytes of text we show; less than SHOW if we need room for the ellipsis.
+        budget : usize,
+        // Set once a character didn't fit, so that later, shorter ones aren't shown out of order.
+        full : bool,
+        shown : ArrayString<SHOW>,
+    },
+    Done,
+}
+
+const ELLIPSIS : &str = "\u{2026}";
+
+// The length of a UTF-8 encoded character from its first byte, or None if it can't start one.
+fn utf8_char_len(first: u8) -> Option<usize> {
+    match first {
+        0x00..=0x7f => Some(1),
+        0xc2..=0xdf => Some(2),
+        0xe0..=0xef => Some(3),
+        0xf0..=0xf4 => Some(4),
+        _ => None,
+    }
+}
+
+impl<N, const MAX : usize, const SHOW : usize> ParserCommon<DArray<N, Byte, MAX>> for DisplayString<MAX, SHOW> where
+    DefaultInterp : ParserCommon<N> {
+    type State = DisplayStringState<<DefaultInterp as ParserCommon<N>>::State, <DefaultInterp as ParserCommon<N>>::Returning, SHOW>;
+    type Returning = ArrayString<SHOW>;
+    fn init(&self) -> Self::State {
+        let () = Self::ROOM_FOR_ELLIPSIS;
+        DisplayStringState::Length(<DefaultInterp as ParserCommon<N>>::init(&DefaultInterp), None)
+    }
+}
+
+impl<N, const MAX : usize, const SHOW : usize> InterpParser<DArray<N, Byte, MAX>> for DisplayString<MAX, SHOW> where
+    DefaultInterp : InterpParser<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>,
+    <DefaultInterp as ParserCommon<N>>::Returning: Copy {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        use DisplayStringState::*;
+        let mut cursor = chunk;
+        loop {
+            match state {
+                Length(ref mut nstate, ref mut length_out) => {
+                    cursor = <DefaultInterp as InterpParser<N>>::parse(&DefaultInterp, nstate, cursor, length_out)?;
+                    let len = <usize as TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>>::try_from(length_out.ok_or(rej(cursor))?).or(Err((Some(OOB::LengthOverflow), cursor)))?;
+                    if len > MAX {
+                        return Err((Some(OOB::TooManyElements), cursor));
+                    }
+                    let budget = if len <= SHOW { SHOW } else { SHOW - ELLIPSIS.len() };
+                    set_from_thunk(state, || Body { remaining: len, pending: ArrayVec::new(), budget, full: false, shown: ArrayString::new() });
+                }
+                Body { ref mut remaining, ref mut pending, budget, ref mut full, ref mut shown } => {
+                    while *remaining > 0 {
+                        let (byte, rest) = cursor.split_first().ok_or((None, cursor))?;
+                        let char_len = utf8_char_len(*pending.first().unwrap_or(byte)).ok_or(rej(cursor))?;
+                        pending.push(*byte);
+                        *remaining -= 1;
+                        if pending.len() == char_len {
+                            // This also catches bad continuation bytes, overlong forms and surrogates.
+                            let c = core::str::from_utf8(pending).or(Err(rej(cursor)))?;
+                            if !*full && shown.len() + c.len() <= *budget {
+                                shown.push_str(c);
+                            } else {
+                                *full = true;
+                            }
+                            pending.clear();
+                        }
+                        cursor = rest;
+                    }
+                    // The length ended partway through a character.
+                    if !pending.is_empty() {
+                        return Err(rej(cursor));
+                    }
+                    if *full {
+                        shown.try_push_str(ELLIPSIS).or(Err(rej(cursor)))?;
+                    }
+                    *destination = match core::mem::replace(state, Done) { Body { shown, .. } => Some(shown), _ => return Err(rej(cursor)) };
+                    return Ok(cursor);
+                }
+                Done => return Err(rej(cursor)),
+            }
+        }
+    }
+}
+
+pub struct DerLengthState {
+    // None until the first byte is read, then the number of long-form bytes still to read.
+    bytes_left : Option<usize>,
+    value : usize,
+}
+
+impl ParserCommon<DerLength> for DefaultInterp {
+    type State = DerLengthState;
+    type Returning = usize;
+    fn init(&self) -> Self::State {
+        DerLengthState { bytes_left: None, value: 0 }
+    }
+}
+
+impl InterpParser<DerLength> for DefaultInterp {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        let mut cursor = chunk;
+        loop {
+            let (byte, rest) = cursor.split_first().ok_or((None, cursor))?;
+            match state.bytes_left {
+                None => {
+                    if *byte < 0x80 {
+                        *destination = Some(*byte as usize);
+                        return Ok(rest);
+                    }
+                    let count = (*byte & 0x7f) as usize;
+                    // 0x80 is the indefinite form, which DER forbids, and 0xff is reserved.
+                    if count == 0 || *byte == 0xff || count > core::mem::size_of::<usize>() {
+                        return Err(rej(cursor));
+                    }
+                    state.bytes_left = Some(count);
+                }
+                Some(ref mut bytes_left) => {
+                    // A leading zero byte means fewer bytes would have done.
+                    if state.value == 0 && *byte == 0 {
+                        return Err(rej(cursor));
+                    }
+                    state.value = (state.value << 8) | *byte as usize;
+                    *bytes_left -= 1;
+                    if *bytes_left == 0 {
+                        // So would the short form.
+                        if state.value < 0x80 {
+                            return Err(rej(cursor));
+                        }
+                        *destination = Some(state.value);
+                        return Ok(rest);
+                    }
+                }
+            }
+            cursor = rest;
+        }
+    }
+}
+
+pub enum ForwardDArrayParserState<N, IS, I, const M : usize > {
+    Length(N),
+    Elements(ArrayVec<I, M>, usize, IS, Option<I>),
+    Done
+}
+
+impl<N, IS, I, const M : usize> ForwardDArrayParserState<N, IS, I, M> {
+    // How many elements have been parsed so far and how many there are in total, once the length
+    // has been read.
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        match self {
+            ForwardDArrayParserState::Elements(vec, len, _, _) => Some((vec.len(), *len)),
+            _ => None,
+        }
+    }
+}
+
+// Parses (part of) the element at index with sub. A plain rejection is reported with the index,
+// pointing at the start of the element, or of the part of it in this chunk; more specific reasons
+// are passed through unchanged.
+fn parse_element<'a, I, S : InterpParser<I>>(sub: &S, index: usize, istate: &mut <S as ParserCommon<I>>::State, cursor: &'a [u8], destination: &mut Option<<S as ParserCommon<I>>::Returning>) -> ParseResult<'a> {
+    match sub.parse(istate, cursor, destination) {
+        Err((Some(OOB::Reject), _)) => Err((Some(OOB::RejectedElement(index)), cursor)),
+        result => result,
+    }
+}
+
+use core::convert::TryFrom;
+
+// Parses (the rest of) a DArray's length prefix, advancing cursor past it, and rejects with
+// LengthOverflow if it doesn't fit in a usize and with TooManyElements if it is more than max,
+// before any elements are read.
+fn parse_darray_length<'a, N>(nstate: &mut <DefaultInterp as ParserCommon<N>>::State, cursor: &mut &'a [u8], max: usize) -> Result<usize, (PResult<OOB>, RemainingSlice<'a>)> where
+    DefaultInterp : InterpParser<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning> {
+    let mut length_out = None;
+    *cursor = <DefaultInterp as InterpParser<N>>::parse(&DefaultInterp, nstate, cursor, &mut length_out)?;
+    let len = <usize as TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>>::try_from(length_out.ok_or(rej(cursor))?).or(Err((Some(OOB::LengthOverflow), *cursor)))?;
+    if len > max {
+        return Err((Some(OOB::TooManyElements), cursor));
+    }
+    Ok(len)
+}
+
+impl<N, I, S : ParserCommon<I>, const M : usize> ParserCommon<DArray<N, I, M> > for SubInterp<S> where
+    DefaultInterp : ParserCommon<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>,
+    <S as ParserCommon<I>>::Returning: Clone{
+    type State=ForwardDArrayParserState<<DefaultInterp as ParserCommon<N>>::State, <S as ParserCommon<I>>::State, <S as ParserCommon<I>>::Returning, M>;
+    type Returning = ArrayVec<<S as ParserCommon<I>>::Returning, M>;
+    fn init(&self) -> Self::State {
+        Self::State::Length(<DefaultInterp as ParserCommon<N>>::init(&DefaultInterp))
+    }
+}
+
+impl<N, I, S : InterpParser<I>, const M : usize> InterpParser<DArray<N, I, M> > for SubInterp<S> where
+    DefaultInterp : InterpParser<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>,
+    <S as ParserCommon<I>>::Returning: Clone{
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        parse_darray::<N, I, S, M>(&self.0, state, chunk, destination)
+    }
+}
+
+// Shared by SubInterp and DArrayOptional, which only differ in what they make of the elements.
+#[inline(never)]
+fn parse_darray<'a, N, I, S : InterpParser<I>, const M : usize>(
+    sub: &S,
+    state: &mut ForwardDArrayParserState<<DefaultInterp as ParserCommon<N>>::State, <S as ParserCommon<I>>::State, <S as ParserCommon<I>>::Returning, M>,
+    chunk: &'a [u8],
+    destination: &mut Option<ArrayVec<<S as ParserCommon<I>>::Returning, M>>,
+) -> ParseResult<'a> where
+    DefaultInterp : InterpParser<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning> {
+    use ForwardDArrayParserState::*;
+    let mut cursor : &'a [u8] = chunk;
+    loop {
+        match state {
+            Length(ref mut nstate) => {
+                // Don't bother reading elements we have no room for.
+                let len = parse_darray_length::<N>(nstate, &mut cursor, M)?;
+                set_from_thunk(state, || Elements(ArrayVec::new(), len, <S as ParserCommon<I>>::init(sub), None));
+            }
+            Elements(ref mut vec, len, ref mut istate, ref mut sub_destination) => {
+                while vec.len() < *len {
+                    cursor = parse_element(sub, vec.len(), istate, cursor, sub_destination)?;
+                    vec.try_push(core::mem::take(sub_destination).ok_or((Some(OOB::Reject), cursor))?).or(Err((Some(OOB::Reject), cursor)))?;
+                    *istate = <S as ParserCommon<I>>::init(sub);
+                }
+                *destination = match core::mem::replace(state, Done) { Elements(vec, _, _, _) => Some(vec), _ => break Err((Some(OOB::Reject), cursor)), };
+                break Ok(cursor);
+            }
+            Done => { break Err((Some(OOB::Reject), cursor)); }
+        }
+    }
+}
+
+
+// Shared by the DArray interpretations that check each element against the one before it as it
+// is parsed, rejecting with the OOB from check as soon as it fails rather than after collecting
+// everything.
+#[inline(never)]
+fn parse_darray_ordered<'a, N, I, S : InterpParser<I>, const M : usize>(
+    sub: &S,
+    state: &mut ForwardDArrayParserState<<DefaultInterp as ParserCommon<N>>::State, <S as ParserCommon<I>>::State, <S as ParserCommon<I>>::Returning, M>,
+    chunk: &'a [u8],
+    destination: &mut Option<ArrayVec<<S as ParserCommon<I>>::Returning, M>>,
+    check: fn(&<S as ParserCommon<I>>::Returning, &<S as ParserCommon<I>>::Returning) -> Result<(), OOB>,
+) -> ParseResult<'a> where
+    DefaultInterp : InterpParser<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning> {
+    use ForwardDArrayParserState::*;
+    let mut cursor : &'a [u8] = chunk;
+    loop {
+        match state {
+            Length(ref mut nstate) => {
+                let len = parse_darray_length::<N>(nstate, &mut cursor, M)?;
+                set_from_thunk(state, || Elements(ArrayVec::new(), len, <S as ParserCommon<I>>::init(sub), None));
+            }
+            Elements(ref mut vec, len, ref mut istate, ref mut sub_destination) => {
+                while vec.len() < *len {
+                    cursor = parse_element(sub, vec.len(), istate, cursor, sub_destination)?;
+                    let item = core::mem::take(sub_destination).ok_or(rej(cursor))?;
+                    if let Some(prev) = vec.last() {
+                        check(prev, &item).map_err(|oob| (Some(oob), cursor))?;
+                    }
+                    vec.try_push(item).or(Err(rej(cursor)))?;
+                    *istate = <S as ParserCommon<I>>::init(sub);
+                }
+                *destination = match core::mem::replace(state, Done) { Elements(vec, _, _, _) => Some(vec), _ => break Err(rej(cursor)), };
+                break Ok(cursor);
+            }
+            Done => { break Err(rej(cursor)); }
+        }
+    }
+}
+
+// Like SubInterp over DArray, but rejects if an element is less than the one before it.
+pub struct DArraySorted<S>(pub S);
+
+impl<N, I, S : ParserCommon<I>, const M : usize> ParserCommon<DArray<N, I, M> > for DArraySorted<S> where
+    DefaultInterp : ParserCommon<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>,
+    <S as ParserCommon<I>>::Returning: PartialOrd {
+    type State=ForwardDArrayParserState<<DefaultInterp as ParserCommon<N>>::State, <S as ParserCommon<I>>::State, <S as ParserCommon<I>>::Returning, M>;
+    type Returning = ArrayVec<<S as ParserCommon<I>>::Returning, M>;
+    fn init(&self) -> Self::State {
+        Self::State::Length(<DefaultInterp as ParserCommon<N>>::init(&DefaultInterp))
+    }
+}
+
+impl<N, I, S : InterpParser<I>, const M : usize> InterpParser<DArray<N, I, M> > for DArraySorted<S> where
+    DefaultInterp : InterpParser<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>,
+    <S as ParserCommon<I>>::Returning: PartialOrd {
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        parse_darray_ordered::<N, I, S, M>(&self.0, state, chunk, destination, |prev, next| if prev <= next { Ok(()) } else { Err(OOB::Reject) })
+    }
+}
+
+// Like DArraySorted, but the elements must be strictly increasing. Since the input is sorted, any
+// repeated element is next to its twin, so comparing neighbours finds duplicates without needing
+// a set; a repeat rejects with OOB::Duplicate rather than OOB::Reject, so that callers validating
+// e.g. a signer set can tell the two apart.
+pub struct DArrayUnique<S>(pub S);
+
+impl<N, I, S : ParserCommon<I>, const M : usize> ParserCommon<DArray<N, I, M> > for DArrayUnique<S> where
+    DefaultInterp : ParserCommon<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>,
+    <S as ParserCommon<I>>::Returning: PartialOrd {
+    type State=ForwardDArrayParserState<<DefaultInterp as ParserCommon<N>>::State, <S as ParserCommon<I>>::State, <S as ParserCommon<I>>::Returning, M>;
+    type Returning = ArrayVec<<S as ParserCommon<I>>::Returning, M>;
+    fn init(&self) -> Self::State {
+        Self::State::Length(<DefaultInterp as ParserCommon<N>>::init(&DefaultInterp))
+    }
+}
+
+impl<N, I, S : InterpParser<I>, const M : usize> InterpParser<DArray<N, I, M> > for DArrayUnique<S> where
+    DefaultInterp : InterpParser<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>,
+    <S as ParserCommon<I>>::Returning: PartialOrd {
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        parse_darray_ordered::<N, I, S, M>(&self.0, state, chunk, destination, |prev, next| match prev.partial_cmp(next) {
+            Some(core::cmp::Ordering::Less) => Ok(()),
+            Some(core::cmp::Ordering::Equal) => Err(OOB::Duplicate),
+            _ => Err(OOB::Reject),
+        })
+    }
+}
+
+// Like SubInterp over DArray, but for formats that use a count of zero to mean the field is absent:
+// the result is None for a zero count, and the elements otherwise. Any count up to the capacity M
+// is accepted, as for SubInterp, so use a capacity of 1 for an optional value whose count may only
+// be 0 or 1; larger counts are rejected with OOB::TooManyElements.
+pub struct DArrayOptional<S>(pub S);
+
+impl<N, I, S : ParserCommon<I>, const M : usize> ParserCommon<DArray<N, I, M> > for DArrayOptional<S> where
+    DefaultInterp : ParserCommon<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning> {
+    type State=ForwardDArrayParserState<<DefaultInterp as ParserCommon<N>>::State, <S as ParserCommon<I>>::State, <S as ParserCommon<I>>::Returning, M>;
+    type Returning = Option<ArrayVec<<S as ParserCommon<I>>::Returning, M>>;
+    fn init(&self) -> Self::State {
+        Self::State::Length(<DefaultInterp as ParserCommon<N>>::init(&DefaultInterp))
+    }
+}
+
+impl<N, I, S : InterpParser<I>, const M : usize> InterpParser<DArray<N, I, M> > for DArrayOptional<S> where
+    DefaultInterp : InterpParser<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning> {
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        let mut elements = None;
+        let cursor = parse_darray::<N, I, S, M>(&self.0, state, chunk, &mut elements)?;
+        let elements = elements.ok_or(rej(cursor))?;
+        *destination = Some(if elements.is_empty() { None } else { Some(elements) });
+        Ok(cursor)
+    }
+}
+
+// FoldInterp parses each element of an Array or DArray with S and folds it into an accumulator,
+// which starts from the second field and is updated by the third, instead of collecting the
+// elements. Only one element is held at a time, but the capacity M of a DArray is still enforced,
+// as for SubInterp, so the schema means the same thing whichever interpretation is used.
+#[derive(Clone)]
+pub struct FoldInterp<S, X, F>(pub S, pub fn() -> X, pub F);
+
+pub struct FoldArrayParserState<IS, I> {
+    seen : usize,
+    subparser_state : IS,
+    subparser_destination : Option<I>,
+}
+
+impl<I, S : ParserCommon<I>, X, F : Fn(&mut X, <S as ParserCommon<I>>::Returning), const N : usize> ParserCommon<Array<I, N>> for FoldInterp<S, X, F> {
+    type State = FoldArrayParserState<<S as ParserCommon<I>>::State, <S as ParserCommon<I>>::Returning>;
+    type Returning = X;
+    fn init(&self) -> Self::State {
+        FoldArrayParserState { seen: 0, subparser_state: <S as ParserCommon<I>>::init(&self.0), subparser_destination: None }
+    }
+}
+
+impl<I, S : InterpParser<I>, X, F : Fn(&mut X, <S as ParserCommon<I>>::Returning), const N : usize> InterpParser<Array<I, N>> for FoldInterp<S, X, F> {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        // The accumulator is only updated once an element is complete, so seeding it again while the
+        // first element is still in progress is harmless.
+        if state.seen == 0 {
+            set_from_thunk(destination, || Some((self.1)()));
+        }
+        let mut cursor : &'a [u8] = chunk;
+        while state.seen < N {
+            cursor = parse_element(&self.0, state.seen, &mut state.subparser_state, cursor, &mut state.subparser_destination)?;
+            let item = core::mem::take(&mut state.subparser_destination).ok_or(rej(cursor))?;
+            (self.2)(destination.as_mut().ok_or(rej(cursor))?, item);
+            state.seen += 1;
+            state.subparser_state = <S as ParserCommon<I>>::init(&self.0);
+        }
+        Ok(cursor)
+    }
+}
+
+pub enum FoldDArrayParserState<N, IS, I> {
+    Length(N),
+    Elements(usize, usize, IS, Option<I>),
+}
+
+impl<N, I, S : ParserCommon<I>, X, F : Fn(&mut X, <S as ParserCommon<I>>::Returning), const M : usize> ParserCommon<DArray<N, I, M>> for FoldInterp<S, X, F> where
+    DefaultInterp : ParserCommon<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning> {
+    type State = FoldDArrayParserState<<DefaultInterp as ParserCommon<N>>::State, <S as ParserCommon<I>>::State, <S as ParserCommon<I>>::Returning>;
+    type Returning = X;
+    fn init(&self) -> Self::State {
+        FoldDArrayParserState::Length(<DefaultInterp as ParserCommon<N>>::init(&DefaultInterp))
+    }
+}
+
+impl<N, I, S : InterpParser<I>, X, F : Fn(&mut X, <S as ParserCommon<I>>::Returning), const M : usize> InterpParser<DArray<N, I, M>> for FoldInterp<S, X, F> where
+    DefaultInterp : InterpParser<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning> {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        use FoldDArrayParserState::*;
+        let mut cursor : &'a [u8] = chunk;
+        loop {
+            match state {
+                Length(ref mut nstate) => {
+                    let len = parse_darray_length::<N>(nstate, &mut cursor, M)?;
+                    set_from_thunk(destination, || Some((self.1)()));
+                    set_from_thunk(state, || Elements(0, len, <S as ParserCommon<I>>::init(&self.0), None));
+                }
+                Elements(ref mut seen, len, ref mut istate, ref mut sub_destination) => {
+                    while *seen < *len {
+                        cursor = parse_element(&self.0, *seen, istate, cursor, sub_destination)?;
+                        let item = core::mem::take(sub_destination).ok_or(rej(cursor))?;
+                        (self.2)(destination.as_mut().ok_or(rej(cursor))?, item);
+                        *seen += 1;
+                        *istate = <S as ParserCommon<I>>::init(&self.0);
+                    }
+                    break Ok(cursor);
+                }
+            }
+        }
+    }
+}
+
+// Like SubInterp over DArray, but appends elements directly to the ArrayVec in the destination
+// instead of building one in our own state and moving it out at the end. The state is then only
+// the length and the element subparser's state, rather than also M elements, and no copy of the
+// finished array is made; for a DArray<_, U32<_>, 64> that is 256 bytes less state and one
+// fewer 256 byte move.
+//
+// Unlike SubInterp, the destination is Some and partially filled while parsing is in progress.
+pub struct SubInterpInPlace<S>(pub S);
+
+pub enum InPlaceDArrayParserState<N, IS, I> {
+    Length(N),
+    Elements(usize, IS, Option<I>),
+    Done
+}
+
+impl<N, I, S : ParserCommon<I>, const M : usize> ParserCommon<DArray<N, I, M> > for SubInterpInPlace<S> where
+    DefaultInterp : ParserCommon<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning> {
+    type State=InPlaceDArrayParserState<<DefaultInterp as ParserCommon<N>>::State, <S as ParserCommon<I>>::State, <S as ParserCommon<I>>::Returning>;
+    type Returning = ArrayVec<<S as ParserCommon<I>>::Returning, M>;
+    fn init(&self) -> Self::State {
+        Self::State::Length(<DefaultInterp as ParserCommon<N>>::init(&DefaultInterp))
+    }
+}
+
+impl<N, I, S : InterpParser<I>, const M : usize> InterpParser<DArray<N, I, M> > for SubInterpInPlace<S> where
+    DefaultInterp : InterpParser<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning> {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        use InPlaceDArrayParserState::*;
+        let mut cursor : &'a [u8] = chunk;
+        loop {
+            match state {
+                Length(ref mut nstate) => {
+                    let len = parse_darray_length::<N>(nstate, &mut cursor, M)?;
+                    set_from_thunk(destination, || Some(ArrayVec::new()));
+                    set_from_thunk(state, || Elements(len, <S as ParserCommon<I>>::init(&self.0), None));
+                }
+                Elements(len, ref mut istate, ref mut sub_destination) => {
+                    let vec = destination.as_mut().ok_or(rej(cursor))?;
+                    while vec.len() < *len {
+                        cursor = parse_element(&self.0, vec.len(), istate, cursor, sub_destination)?;
+                        vec.try_push(core::mem::take(sub_destination).ok_or(rej(cursor))?).or(Err(rej(cursor)))?;
+                        *istate = <S as ParserCommon<I>>::init(&self.0);
+                    }
+                    set_from_thunk(state, || Done);
+                    break Ok(cursor);
+                }
+                Done => { break Err(rej(cursor)); }
+            }
+        }
+    }
+}
+
+// Like SubInterp over DArray, but when the declared length is more than M, keeps the first M
+// elements and structurally skips the rest with DropInterp instead of rejecting.
+pub struct DArrayTruncating<S>(pub S);
+
+pub enum TruncatingDArrayParserState<N, IS, DS, I, const M : usize > {
+    Length(N),
+    Elements(ArrayVec<I, M>, usize, IS, Option<I>),
+    Skipping(ArrayVec<I, M>, usize, DS),
+    Done
+}
+
+impl<N, I, S : ParserCommon<I>, const M : usize> ParserCommon<DArray<N, I, M> > for DArrayTruncating<S> where
+    DefaultInterp : ParserCommon<N>,
+    DropInterp : ParserCommon<I>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning> {
+    type State=TruncatingDArrayParserState<<DefaultInterp as ParserCommon<N>>::State, <S as ParserCommon<I>>::State, <DropInterp as ParserCommon<I>>::State, <S as ParserCommon<I>>::Returning, M>;
+    type Returning = ArrayVec<<S as ParserCommon<I>>::Returning, M>;
+    fn init(&self) -> Self::State {
+        Self::State::Length(<DefaultInterp as ParserCommon<N>>::init(&DefaultInterp))
+    }
+}
+
+impl<N, I, S : InterpParser<I>, const M : usize> InterpParser<DArray<N, I, M> > for DArrayTruncating<S> where
+    DefaultInterp : InterpParser<N>,
+    DropInterp : InterpParser<I>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning> {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        use TruncatingDArrayParserState::*;
+        let mut cursor : &'a [u8] = chunk;
+        loop {
+            match state {
+                Length(ref mut nstate) => {
+                    let len = parse_darray_length::<N>(nstate, &mut cursor, usize::MAX)?;
+                    set_from_thunk(state, || Elements(ArrayVec::new(), len, <S as ParserCommon<I>>::init(&self.0), None));
+                }
+                Elements(ref mut vec, len, ref mut istate, ref mut sub_destination) => {
+                    while vec.len() < core::cmp::min(*len, M) {
+                        cursor = parse_element(&self.0, vec.len(), istate, cursor, sub_destination)?;
+                        vec.push(core::mem::take(sub_destination).ok_or(rej(cursor))?);
+                        *istate = <S as ParserCommon<I>>::init(&self.0);
+                    }
+                    let to_skip = *len - vec.len();
+                    match core::mem::replace(state, Done) {
+                        Elements(vec, _, _, _) => set_from_thunk(state, || Skipping(vec, to_skip, <DropInterp as ParserCommon<I>>::init(&DropInterp))),
+                        _ => break Err(rej(cursor)),
+                    }
+                }
+                Skipping(_, ref mut to_skip, ref mut dstate) => {
+                    while *to_skip > 0 {
+                        cursor = <DropInterp as InterpParser<I>>::parse(&DropInterp, dstate, cursor, &mut None)?;
+                        *to_skip -= 1;
+                        *dstate = <DropInterp as ParserCommon<I>>::init(&DropInterp);
+                    }
+                    *destination = match core::mem::replace(state, Done) { Skipping(vec, _, _) => Some(vec), _ => break Err(rej(cursor)), };
+                    break Ok(cursor);
+                }
+                Done => { break Err(rej(cursor)); }
+            }
+        }
+    }
+}
+
+
+impl< I, const N : usize >  ParserCommon<Array<I, N>> for DefaultInterp where
+    DefaultInterp : ParserCommon<I> {
+    type State = <SubInterp<DefaultInterp> as ParserCommon<Array< I, N> >>::State;
+    type Returning = <SubInterp<DefaultInterp> as ParserCommon<Array< I, N> >>::Returning;
+    fn init(&self) -> Self::State {
+        <SubInterp<DefaultInterp> as ParserCommon<Array<I, N>>>::init(&SubInterp(DefaultInterp))
+    }
+}
+
+impl< I, const N : usize >  InterpParser<Array<I, N>> for DefaultInterp where
+    DefaultInterp : InterpParser<I> {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        <SubInterp<DefaultInterp> as InterpParser<Array<I, N>>>::parse(&SubInterp(DefaultInterp), state, chunk, destination)
+    }
+}
+
+
+impl< N, I, const M : usize> ParserCommon<DArray<N, I, M>> for DefaultInterp where
+    DefaultInterp : ParserCommon<I> + ParserCommon<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>,
+    <DefaultInterp as ParserCommon<I>>::Returning: Clone {
+    type State = <SubInterp<DefaultInterp> as ParserCommon<DArray<N, I, M>>>::State;
+    type Returning = <SubInterp<DefaultInterp> as ParserCommon<DArray<N, I, M>>>::Returning;
+    fn init(&self) -> Self::State {
+        <SubInterp<DefaultInterp> as ParserCommon<DArray<N, I, M>>>::init(&SubInterp(DefaultInterp))
+    }
+}
+
+impl< N, I, const M : usize> InterpParser<DArray<N, I, M>> for DefaultInterp where
+    DefaultInterp : InterpParser<I> + InterpParser<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>,
+    <DefaultInterp as ParserCommon<I>>::Returning: Clone {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        <SubInterp<DefaultInterp> as InterpParser<DArray<N, I, M>>>::parse(&SubInterp(DefaultInterp), state, chunk, destination)
+    }
+}
+
+// The state of Action and the parsers like it: S's state and S's result.
+type ActionState<A, S> = (<S as ParserCommon<A> >::State, Option<<S as ParserCommon<A> >::Returning>);
+// The same for the parameterized Action and ParamAction, which also hold the parameter.
+type ParamActionState<A, S, C> = (<S as ParserCommon<A> >::State, Option<<S as ParserCommon<A> >::Returning>, Option<C>);
+
+fn init_action_state_in_place<A, S : ParserCommon<A>>(sub: &S, state: *mut core::mem::MaybeUninit<ActionState<A, S>>) {
+   sub.init_in_place(unsafe { core::ptr::addr_of_mut!((*(*state).as_mut_ptr()).0) as *mut core::mem::MaybeUninit<<S as ParserCommon<A> >::State> });
+   call_fn( || unsafe { core::ptr::addr_of_mut!((*(*state).as_mut_ptr()).1).write(None)} );
+}
+
+fn init_param_action_state_in_place<A, S : ParserCommon<A>, C>(sub: &S, state: *mut core::mem::MaybeUninit<ParamActionState<A, S, C>>) {
+   sub.init_in_place(unsafe { core::ptr::addr_of_mut!((*(*state).as_mut_ptr()).0) as *mut core::mem::MaybeUninit<<S as ParserCommon<A> >::State> });
+   call_fn( || unsafe { core::ptr::addr_of_mut!((*(*state).as_mut_ptr()).1).write(None)} );
+   call_fn( || unsafe { core::ptr::addr_of_mut!((*(*state).as_mut_ptr()).2).write(None)} );
+}
+
+// Action is essentailly an fmap that can fail.
+// We _could_ constraint F to actually be an fn(..) -> Option<()> to improve error messages when
+// functions do not have the correct shape, but that reduces our ability to write different
+// instances later.
+#[derive(Clone)]
+pub struct Action<S, F>(pub S, pub F);
+
+impl<A, R, S : ParserCommon<A>> ParserCommon<A> for Action<S, fn(&<S as ParserCommon<A>>::Returning, &mut Option<R>) -> Option<()>>
+{
+    type State = (<S as ParserCommon<A> >::State, Option<<S as ParserCommon<A> >::Returning>);
+    type Returning = R;
+
+    // #[inline(never)] // Causes stack size increase
+    fn init(&self) -> Self::State {
+        (<S as ParserCommon<A>>::init(&self.0), None)
+    }
+
+    #[inline(never)]
+    fn init_in_place(&self, state: *mut core::mem::MaybeUninit<Self::State>) {
+        init_action_state_in_place(&self.0, state);
+    }
+}
+
+impl<A, R, S : InterpParser<A>> InterpParser<A> for Action<S, fn(&<S as ParserCommon<A>>::Returning, &mut Option<R>) -> Option<()>>
+{
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        let new_chunk = self.0.parse(&mut state.0, chunk, &mut state.1)?;
+        match (self.1)(state.1.as_ref().ok_or((Some(OOB::Reject),new_chunk))?, destination) {
+            None => { Err((Some(OOB::Reject),new_chunk)) }
+            Some(()) => { Ok(new_chunk) }
+        }
+    }
+}
+
+impl<A, R, S : DynParser<A>> DynParser<A> for Action<S, fn(&<S as ParserCommon<A>>::Returning, &mut Option<R>) -> Option<()>>
+    {
+        type Parameter = S::Parameter;
+        #[inline(never)]
+        fn init_param(&self, param: Self::Parameter, state: &mut Self::State, _destination: &mut Option<Self::Returning>) {
+            set_from_thunk(&mut state.0, || <S as ParserCommon<A>>::init(&self.0));
+            set_from_thunk(&mut state.1, || None);
+            self.0.init_param(param, &mut state.0, &mut state.1);
+        }
+    }
+
+/* This impl exists to allow the _function_ of an Action to be the target of the parameter for
+ * DynParser, thus giving an escape hatch to thread a parameter past a non-parameterized
+ * parser. Whether this should still be an Action as opposed to some other name is not immediately
+ * clear.
+ *
+ * Note the difference from the two-argument Action above: there the parameter goes to S and the
+ * function never sees it, while here it goes only to the function and S is initialized without
+ * it. To give it to both, use ParamAction. */
+impl<A, R, S : ParserCommon<A>, C> ParserCommon<A> for Action<S, fn(&<S as
+    ParserCommon<A>>::Returning, &mut Option<R>, C) -> Option<()>>
+{
+    type State = (<S as ParserCommon<A> >::State, Option<<S as ParserCommon<A> >::Returning>, Option<C>);
+    type Returning = R;
+
+    #[inline(never)]
+    fn init(&self) -> Self::State {
+        (<S as ParserCommon<A>>::init(&self.0), None, None)
+    }
+
+    #[inline(never)]
+    fn init_in_place(&self, state: *mut core::mem::MaybeUninit<Self::State>) {
+        init_param_action_state_in_place(&self.0, state);
+    }
+}
+
+impl<A, R, S : InterpParser<A>, C> InterpParser<A> for Action<S, fn(&<S as ParserCommon<A>>::Returning, &mut Option<R>, C) -> Option<()>>
+{
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        parse_param_action(&self.0, self.1, state, chunk, destination)
+    }
+}
+
+impl<A, R, S : ParserCommon<A>, C> DynParser<A> for Action<S, fn(&<S as ParserCommon<A>>::Returning, &mut Option<R>, C) -> Option<()>>
+    {
+        type Parameter = C;
+        #[inline(never)]
+        fn init_param(&self, param: Self::Parameter, state: &mut Self::State, _destination: &mut Option<Self::Returning>) {
+            set_from_thunk(&mut state.0, || <S as ParserCommon<A>>::init(&self.0));
+            set_from_thunk(&mut state.1, || None);
+            set_from_thunk(&mut state.2, || Some(param));
+        }
+    }
+
+// The parse shared by the parameterized Action and ParamAction: once S has finished, the function
+// gets its result by reference along with the parameter, which is used up.
+fn parse_param_action<'a, A, R, S : InterpParser<A>, C>(sub: &S, f: fn(&<S as ParserCommon<A>>::Returning, &mut Option<R>, C) -> Option<()>, state: &mut ParamActionState<A, S, C>, chunk: &'a [u8], destination: &mut Option<R>) -> ParseResult<'a> {
+    let new_chunk = sub.parse(&mut state.0, chunk, &mut state.1)?;
+    match f(state.1.as_ref().ok_or((Some(OOB::Reject),new_chunk))?, destination, core::mem::take(&mut state.2).ok_or((Some(OOB::Reject),new_chunk))?) {
+        None => { Err((Some(OOB::Reject),new_chunk)) }
+        Some(()) => { Ok(new_chunk) }
+    }
+}
+
+/* ParamAction is the three-argument Action for when S needs the parameter as well as the
+ * function: init_param passes a clone of it to S's own init_param, and the original is handed to
+ * the function once S has finished. */
+#[derive(Clone)]
+pub struct ParamAction<S, F>(pub S, pub F);
+
+impl<A, R, S : ParserCommon<A>, C> ParserCommon<A> for ParamAction<S, fn(&<S as ParserCommon<A>>::Returning, &mut Option<R>, C) -> Option<()>>
+{
+    type State = (<S as ParserCommon<A> >::State, Option<<S as ParserCommon<A> >::Returning>, Option<C>);
+    type Returning = R;
+
+    #[inline(never)]
+    fn init(&self) -> Self::State {
+        (<S as ParserCommon<A>>::init(&self.0), None, None)
+    }
+
+    #[inline(never)]
+    fn init_in_place(&self, state: *mut core::mem::MaybeUninit<Self::State>) {
+        init_param_action_state_in_place(&self.0, state);
+    }
+}
+
+impl<A, R, S : InterpParser<A>, C> InterpParser<A> for ParamAction<S, fn(&<S as ParserCommon<A>>::Returning, &mut Option<R>, C) -> Option<()>>
+{
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        parse_param_action(&self.0, self.1, state, chunk, destination)
+    }
+}
+
+impl<A, R, S : DynParser<A, Parameter = C>, C : Clone> DynParser<A> for ParamAction<S, fn(&<S as ParserCommon<A>>::Returning, &mut Option<R>, C) -> Option<()>>
+    {
+        type Parameter = C;
+        #[inline(never)]
+        fn init_param(&self, param: Self::Parameter, state: &mut Self::State, _destination: &mut Option<Self::Returning>) {
+            set_from_thunk(&mut state.0, || <S as ParserCommon<A>>::init(&self.0));
+            set_from_thunk(&mut state.1, || None);
+            self.0.init_param(param.clone(), &mut state.0, &mut state.1);
+            set_from_thunk(&mut state.2, || Some(param));
+        }
+    }
+
+// The parse shared by MoveAction and the parsers like it: once S has finished, f gets its result
+// by value. A rejection from f points after the value, or if at_start, where this call began.
+fn parse_move_action<'a, A, S : InterpParser<A>>(sub: &S, state: &mut ActionState<A, S>, chunk: &'a [u8], at_start: bool, f: impl FnOnce(<S as ParserCommon<A>>::Returning) -> Option<()>) -> ParseResult<'a> {
+    let new_chunk = sub.parse(&mut state.0, chunk, &mut state.1)?;
+    match f(core::mem::take(&mut state.1).ok_or((Some(OOB::Reject),new_chunk))?) {
+        None => { Err((Some(OOB::Reject), if at_start { chunk } else { new_chunk })) }
+        Some(()) => { Ok(new_chunk) }
+    }
+}
+
+/* A MoveAction is the same as an Action with the distinction that it takes it's argument via Move,
+ * thus enabling it to work with types that do not have Copy or Clone and have nontrivial semantics
+ * involving Drop. */
+pub struct MoveAction<S, F>(pub S, pub F);
+impl<A, R, S : ParserCommon<A>> ParserCommon<A> for MoveAction<S, fn(<S as ParserCommon<A>>::Returning, &mut Option<R>) -> Option<()>>
+{
+    type State = (<S as ParserCommon<A> >::State, Option<<S as ParserCommon<A> >::Returning>);
+    type Returning = R;
+
+    #[inline(never)]
+    fn init(&self) -> Self::State {
+        (<S as ParserCommon<A>>::init(&self.0), None)
+    }
+
+    #[inline(never)]
+    fn init_in_place(&self, state: *mut core::mem::MaybeUninit<Self::State>) {
+        init_action_state_in_place(&self.0, state);
+    }
+}
+
+impl<A, R, S : InterpParser<A>> InterpParser<A> for MoveAction<S, fn(<S as ParserCommon<A>>::Returning, &mut Option<R>) -> Option<()>>
+{
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        parse_move_action(&self.0, state, chunk, false, |value| (self.1)(value, destination))
+    }
+}
+
+impl<A, R, S : DynParser<A>> DynParser<A> for MoveAction<S, fn(<S as ParserCommon<A>>::Returning, &mut Option<R>) -> Option<()>>
+    {
+        type Parameter = S::Parameter;
+        #[inline(never)]
+        fn init_param(&self, param: Self::Parameter, state: &mut Self::State, _destination: &mut Option<Self::Returning>) {
+            set_from_thunk(&mut state.0, || <S as ParserCommon<A>>::init(&self.0));
+            set_from_thunk(&mut state.1, || None);
+            self.0.init_param(param, &mut state.0, &mut state.1);
+        }
+    }
+
+/* MoveActionAtStart is a MoveAction whose rejections from F point at the input where this call to
+ * parse began rather than after the value, so that a validation failure indicates the value
+ * itself. If the value was split across chunks, that is the start of the current chunk. */
+pub struct MoveActionAtStart<S, F>(pub S, pub F);
+impl<A, R, S : ParserCommon<A>> ParserCommon<A> for MoveActionAtStart<S, fn(<S as ParserCommon<A>>::Returning, &mut Option<R>) -> Option<()>>
+{
+    type State = (<S as ParserCommon<A> >::State, Option<<S as ParserCommon<A> >::Returning>);
+    type Returning = R;
+
+    #[inline(never)]
+    fn init(&self) -> Self::State {
+        (<S as ParserCommon<A>>::init(&self.0), None)
+    }
+
+    #[inline(never)]
+    fn init_in_place(&self, state: *mut core::mem::MaybeUninit<Self::State>) {
+        init_action_state_in_place(&self.0, state);
+    }
+}
+
+impl<A, R, S : InterpParser<A>> InterpParser<A> for MoveActionAtStart<S, fn(<S as ParserCommon<A>>::Returning, &mut Option<R>) -> Option<()>>
+{
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        parse_move_action(&self.0, state, chunk, true, |value| (self.1)(value, destination))
+    }
+}
+
+impl<A, R, S : DynParser<A>> DynParser<A> for MoveActionAtStart<S, fn(<S as ParserCommon<A>>::Returning, &mut Option<R>) -> Option<()>>
+    {
+        type Parameter = S::Parameter;
+        #[inline(never)]
+        fn init_param(&self, param: Self::Parameter, state: &mut Self::State, _destination: &mut Option<Self::Returning>) {
+            set_from_thunk(&mut state.0, || <S as ParserCommon<A>>::init(&self.0));
+            set_from_thunk(&mut state.1, || None);
+            self.0.init_param(param, &mut state.0, &mut state.1);
+        }
+    }
+
+/* Where a VisitAction sends the items its function emits. Returning None rejects the parse, e.g.
+ * when a fixed-size buffer is full. */
+pub trait Sink<Item> {
+    fn put(&mut self, item: Item) -> Option<()>;
+}
+
+impl<Item, const N : usize> Sink<Item> for ArrayVec<Item, N> {
+    fn put(&mut self, item: Item) -> Option<()> {
+        self.try_push(item).ok()
+    }
+}
+
+/* VisitAction is a MoveAction whose function emits any number of items to a sink instead of
+ * setting a single result, so that e.g. the lines of a prompt can be passed on one at a time
+ * rather than collected first. The sink is the result: if the destination is already Some, items
+ * go to the sink there, otherwise one is made with the second field. Through DynParser, the sink
+ * can instead be supplied as the parameter. */
+pub struct VisitAction<S, K, F>(pub S, pub fn() -> K, pub F);
+
+impl<A, Item, K : Sink<Item>, S : ParserCommon<A>> ParserCommon<A> for VisitAction<S, K, fn(<S as ParserCommon<A>>::Returning, &mut dyn Sink<Item>) -> Option<()>>
+{
+    type State = (<S as ParserCommon<A> >::State, Option<<S as ParserCommon<A> >::Returning>);
+    type Returning = K;
+
+    #[inline(never)]
+    fn init(&self) -> Self::State {
+        (<S as ParserCommon<A>>::init(&self.0), None)
+    }
+
+    #[inline(never)]
+    fn init_in_place(&self, state: *mut core::mem::MaybeUninit<Self::State>) {
+        init_action_state_in_place(&self.0, state);
+    }
+}
+
+impl<A, Item, K : Sink<Item>, S : InterpParser<A>> InterpParser<A> for VisitAction<S, K, fn(<S as ParserCommon<A>>::Returning, &mut dyn Sink<Item>) -> Option<()>>
+{
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        parse_move_action(&self.0, state, chunk, false, |value| {
+            if destination.is_none() {
+                set_from_thunk(destination, || Some((self.1)()));
+            }
+            (self.2)(value, destination.as_mut()?)
+        })
+    }
+}
+
+impl<A, Item, K : Sink<Item>, S : ParserCommon<A>> DynParser<A> for VisitAction<S, K, fn(<S as ParserCommon<A>>::Returning, &mut dyn Sink<Item>) -> Option<()>>
+    {
+        type Parameter = K;
+        #[inline(never)]
+        fn init_param(&self, param: Self::Parameter, state: &mut Self::State, destination: &mut Option<Self::Returning>) {
+            set_from_thunk(&mut state.0, || <S as ParserCommon<A>>::init(&self.0));
+            set_from_thunk(&mut state.1, || None);
+            set_from_thunk(destination, || Some(param));
+        }
+    }
+
+/* InRange parses a value with S and rejects unless it lies within the inclusive bounds given by
+ * the second and third fields. The value itself is returned unchanged. */
+#[derive(Clone)]
+pub struct InRange<S, R>(pub S, pub R, pub R);
+
+impl<A, R, S : ParserCommon<A, Returning = R>> ParserCommon<A> for InRange<S, R> {
+    type State = <S as ParserCommon<A>>::State;
+    type Returning = R;
+    fn init(&self) -> Self::State {
+        <S as ParserCommon<A>>::init(&self.0)
+    }
+    fn init_in_place(&self, state: *mut core::mem::MaybeUninit<Self::State>) {
+        <S as ParserCommon<A>>::init_in_place(&self.0, state)
+    }
+}
+
+impl<A, R : PartialOrd, S : InterpParser<A, Returning = R>> InterpParser<A> for InRange<S, R> {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        let new_chunk = <S as InterpParser<A>>::parse(&self.0, state, chunk, destination)?;
+        match destination {
+            Some(ref v) if self.1 <= *v && *v <= self.2 => Ok(new_chunk),
+            _ => Err(rej(new_chunk)),
+        }
+    }
+}
+
+impl<A, R, S : DynParser<A, Returning = R>> DynParser<A> for InRange<S, R> {
+    type Parameter = S::Parameter;
+    #[inline(never)]
+    fn init_param(&self, param: Self::Parameter, state: &mut Self::State, destination: &mut Option<Self::Returning>) {
+        self.0.init_param(param, state, destination);
+    }
+}
+
+fn rej<'a>(cnk: &'a [u8]) -> (PResult<OOB>, RemainingSlice<'a>) {
+    (Some(OOB::Reject), cnk)
+}
+
+pub struct Preaction<S>(pub fn() -> Option<()>, pub S);
+
+impl<A, S: ParserCommon<A>> ParserCommon<A> for Preaction<S> {
+    type State = Option<<S as ParserCommon<A>>::State>;
+    type Returning = <S as ParserCommon<A>>::Returning;
+
+    fn init(&self) -> Self::State { None }
+}
+
+impl<A, S: InterpParser<A>> InterpParser<A> for Preaction<S> {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        loop { break match state {
+            None => {
+                (self.0)().ok_or((Some(OOB::Reject), chunk))?;
+                set_from_thunk(state, || Some(<S as ParserCommon<A>>::init(&self.1)));
+                continue;
+            }
+            Some(ref mut s) => <S as InterpParser<A>>::parse(&self.1, s, chunk, destination)
+        }}
+    }
+}
+
+#[derive(Clone)]
+// S is the first subparser to run
+// F is the continuation parser to run, which can depend on the result of S
+pub struct Bind<S, F>(pub S, pub F);
+
+// Initially the state is the state of the first subparser, and its result location
+// After the first subparser runs, if it failed, then the whole bind parser will fail
+// but if it succeeds, then the parser state transitions to BindSecond.
+#[derive(InPlaceInit)]
+pub enum BindState<A,B,S:ParserCommon<A>,T:ParserCommon<B>> {
+    BindFirst(S::State, Option<<S as ParserCommon<A> >::Returning>),
+    BindSecond(T, <T as ParserCommon<B>>::State)
+}
+
+impl<A, B, S : ParserCommon<A>, T : ParserCommon<B>> ParserCommon<(A,B)> for Bind<S, fn(&<S as ParserCommon<A>>::Returning) -> Option<T>>
 {
     type State = BindState<A,B,S,T>;
     type Returning = <T as ParserCommon<B>>::Returning;
@@ -525,6 +1752,29 @@ fn call_fn(f: impl FnOnce()) {
     f()
 }
 
+// Drop an initialized value and have init write a new one in its place, e.g. with one of the init_*
+// functions from InPlaceInit. Parser states can hold values with Drop, e.g. an ArrayVec of results,
+// so the old value has to be dropped rather than just overwritten. MaybeUninit<T> is guaranteed to
+// have the same layout as T, so this is a plain pointer cast and makes no assumption about how the
+// compiler lays out enums; in particular it doesn't rely on the #[repr(u8)] on the states below.
+//
+// While init runs, x is uninitialized, so if init panics we must not unwind past x's owner, which
+// would drop it again. The guard panics a second time in that case, which aborts instead.
+//
+// Safety: init must initialize the value through the pointer it is given.
+unsafe fn reinit_in_place<T>(x: &mut T, init: impl FnOnce(*mut core::mem::MaybeUninit<T>)) {
+    struct AbortOnUnwind;
+    impl Drop for AbortOnUnwind {
+        fn drop(&mut self) {
+            panic!("reinit_in_place: panicked while the state was uninitialized");
+        }
+    }
+    let guard = AbortOnUnwind;
+    core::ptr::drop_in_place(x as *mut T);
+    init((x as *mut T).cast());
+    core::mem::forget(guard);
+}
+
 impl<A, B, S : ParserCommon<A>, T : DynParser<B, Parameter = S::Returning>> ParserCommon<(A,B)> for DynBind<S, T>
 // fn(&<S as InterpParser<A>>::Returning) -> Option<T>>
 {
@@ -558,7 +1808,9 @@ impl<A, B, S : InterpParser<A>, T : DynParser<B, Parameter = S::Returning> + Int
                         } else {
                             unreachable!();
                         }
-                        Self::State::init_bind_second(unsafe { core::mem::transmute(state as *mut Self::State) }, |a| call_fn(|| self.1.init_in_place(a)));
+                        // Overwrite the BindFirst state, whose result we have already taken, with
+                        // BindSecond; the check below then catches a transition that went wrong.
+                        unsafe { reinit_in_place(state, |st| Self::State::init_bind_second(st, |a| call_fn(|| self.1.init_in_place(a)))); }
                         if let BindSecond(ref mut s) = state {
                             self.1.init_param(r_temp, s, destination);
                         } else {
@@ -581,7 +1833,7 @@ impl<A, B, S: DynParser<A>, T: DynParser<B, Parameter = S::Returning>> DynParser
         type Parameter = S::Parameter;
         #[inline(never)]
         fn init_param(&self, param: Self::Parameter, state: &mut Self::State, _destination: &mut Option<Self::Returning>) {
-            self.init_in_place(unsafe { core::mem::transmute(state as *mut Self::State) });
+            unsafe { reinit_in_place(state, |st| self.init_in_place(st)); }
             match state {
                 DynBindState::BindFirst(ref mut s, ref mut sub_destination) => self.0.init_param(param, s, sub_destination),
                 _ => unreachable!(),
@@ -589,6 +1841,204 @@ impl<A, B, S: DynParser<A>, T: DynParser<B, Parameter = S::Returning>> DynParser
         }
     }
 
+// BindEq parses the same schema twice in a row with S, as the pair (A, A), and rejects unless both
+// copies give the same value, for formats that repeat a field as a check. The value is returned
+// once.
+#[derive(Clone)]
+pub struct BindEq<S>(pub S);
+
+pub enum BindEqState<S, R> {
+    First(S, Option<R>),
+    Second(R, S, Option<R>),
+}
+
+impl<A, S : ParserCommon<A>> ParserCommon<(A, A)> for BindEq<S> {
+    type State = BindEqState<<S as ParserCommon<A>>::State, <S as ParserCommon<A>>::Returning>;
+    type Returning = <S as ParserCommon<A>>::Returning;
+    fn init(&self) -> Self::State {
+        BindEqState::First(<S as ParserCommon<A>>::init(&self.0), None)
+    }
+}
+
+impl<A, S : InterpParser<A>> InterpParser<(A, A)> for BindEq<S> where
+    <S as ParserCommon<A>>::Returning : PartialEq {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        use BindEqState::*;
+        let mut cursor = chunk;
+        loop {
+            match state {
+                First(ref mut sub, ref mut first) => {
+                    cursor = self.0.parse(sub, cursor, first)?;
+                    let first = core::mem::take(first).ok_or(rej(cursor))?;
+                    set_from_thunk(state, || Second(first, <S as ParserCommon<A>>::init(&self.0), None));
+                }
+                Second(ref mut first, ref mut sub, ref mut second) => {
+                    cursor = self.0.parse(sub, cursor, second)?;
+                    if second.as_ref() != Some(first) {
+                        return Err(rej(cursor));
+                    }
+                    *destination = core::mem::take(second);
+                    return Ok(cursor);
+                }
+            }
+        }
+    }
+}
+
+// If selects between two parsers with the same result type based on a flag supplied through
+// init_param, usually computed from an earlier field via DynBind. The schema is Alt<A, B>, where A
+// is parsed when the flag is true and B when it is false.
+#[derive(Clone)]
+pub struct If<T, E>(pub T, pub E);
+
+pub enum IfState<T, E> {
+    Unset,
+    Then(T),
+    Else(E),
+}
+
+impl<A, B, T : ParserCommon<A>, E : ParserCommon<B, Returning = T::Returning>> ParserCommon<Alt<A, B>> for If<T, E> {
+    type State = IfState<<T as ParserCommon<A>>::State, <E as ParserCommon<B>>::State>;
+    type Returning = <T as ParserCommon<A>>::Returning;
+    fn init(&self) -> Self::State {
+        IfState::Unset
+    }
+}
+
+impl<A, B, T : InterpParser<A>, E : InterpParser<B, Returning = T::Returning>> InterpParser<Alt<A, B>> for If<T, E> {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        match state {
+            // Nothing told us which branch to take.
+            IfState::Unset => Err(rej(chunk)),
+            IfState::Then(ref mut s) => <T as InterpParser<A>>::parse(&self.0, s, chunk, destination),
+            IfState::Else(ref mut s) => <E as InterpParser<B>>::parse(&self.1, s, chunk, destination),
+        }
+    }
+}
+
+impl<A, B, T : ParserCommon<A>, E : ParserCommon<B, Returning = T::Returning>> DynParser<Alt<A, B>> for If<T, E> {
+    type Parameter = bool;
+    #[inline(never)]
+    fn init_param(&self, param: Self::Parameter, state: &mut Self::State, _destination: &mut Option<Self::Returning>) {
+        if param {
+            set_from_thunk(state, || IfState::Then(<T as ParserCommon<A>>::init(&self.0)));
+        } else {
+            set_from_thunk(state, || IfState::Else(<E as ParserCommon<B>>::init(&self.1)));
+        }
+    }
+}
+
+// ParamSelect generalizes If to more than two parsers with the same result type: the schema is
+// OneOf over a tuple of schemas, and the index of the one to parse is supplied through init_param,
+// typically by DynBind from a header field. An index past the last parser rejects.
+#[derive(Clone)]
+pub struct ParamSelect<T>(pub T);
+
+macro_rules! param_select {
+    { $state:ident $(($p:ident, $s:ident, $v:ident, $i:tt)),+ } => {
+        pub enum $state<$($p),+> {
+            Unset,
+            $($v($p)),+
+        }
+
+        impl<R, $($p : ParserCommon<$s, Returning = R>, $s),+> ParserCommon<OneOf<($($s),+)>> for ParamSelect<($($p),+)> {
+            type State = $state<$(<$p as ParserCommon<$s>>::State),+>;
+            type Returning = R;
+            fn init(&self) -> Self::State {
+                $state::Unset
+            }
+        }
+
+        impl<R, $($p : InterpParser<$s, Returning = R>, $s),+> InterpParser<OneOf<($($s),+)>> for ParamSelect<($($p),+)> {
+            #[inline(never)]
+            fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+                match state {
+                    // Nothing told us which parser to use.
+                    $state::Unset => Err(rej(chunk)),
+                    $($state::$v(ref mut s) => <$p as InterpParser<$s>>::parse(&self.0.$i, s, chunk, destination),)+
+                }
+            }
+        }
+
+        impl<R, $($p : ParserCommon<$s, Returning = R>, $s),+> DynParser<OneOf<($($s),+)>> for ParamSelect<($($p),+)> {
+            type Parameter = usize;
+            #[inline(never)]
+            fn init_param(&self, param: Self::Parameter, state: &mut Self::State, _destination: &mut Option<Self::Returning>) {
+                match param {
+                    $($i => set_from_thunk(state, || $state::$v(<$p as ParserCommon<$s>>::init(&self.0.$i))),)+
+                    _ => set_from_thunk(state, || $state::Unset),
+                }
+            }
+        }
+    }
+}
+
+param_select! { Select2State (A0, S0, Choice0, 0), (A1, S1, Choice1, 1) }
+param_select! { Select3State (A0, S0, Choice0, 0), (A1, S1, Choice1, 1), (A2, S2, Choice2, 2) }
+param_select! { Select4State (A0, S0, Choice0, 0), (A1, S1, Choice1, 1), (A2, S2, Choice2, 2), (A3, S3, Choice3, 3) }
+
+// Declares a Rust enum together with a schema for it: a discriminant read with DefaultInterp at
+// the tag schema, followed by the payload of the variant whose pattern it matches, also read with
+// DefaultInterp. A discriminant matching none of the patterns rejects. For example,
+//
+//     sum_type! { pub enum Instr : Byte { Push(0) : U32<{ Endianness::Big }>, Pop(1) : Array<Byte, 0> } }
+//
+// defines enum Instr { Push(u32), Pop([u8; 0]) } and a schema InstrSchema, with DefaultInterp
+// parsing InstrSchema into an Instr. The parser state is an enum as well, so it is only as large
+// as the tag's or the largest payload's state, not all of them.
+#[macro_export]
+macro_rules! sum_type {
+    { $vis:vis enum $name:ident : $tag:ty { $($variant:ident ( $($val:pat)|+ ) : $schema:ty),+ $(,)? } } => {
+        $crate::json::paste! {
+            #[derive(Debug, PartialEq, Clone)]
+            $vis enum $name {
+                $($variant(<$crate::interp_parser::DefaultInterp as $crate::interp_parser::ParserCommon<$schema>>::Returning)),+
+            }
+
+            $vis struct [<$name Schema>];
+
+            $vis enum [<$name State>] {
+                Tag(<$crate::interp_parser::DefaultInterp as $crate::interp_parser::ParserCommon<$tag>>::State, Option<<$crate::interp_parser::DefaultInterp as $crate::interp_parser::ParserCommon<$tag>>::Returning>),
+                $($variant(<$crate::interp_parser::DefaultInterp as $crate::interp_parser::ParserCommon<$schema>>::State, Option<<$crate::interp_parser::DefaultInterp as $crate::interp_parser::ParserCommon<$schema>>::Returning>)),+
+            }
+
+            impl $crate::interp_parser::ParserCommon<[<$name Schema>]> for $crate::interp_parser::DefaultInterp {
+                type State = [<$name State>];
+                type Returning = $name;
+                fn init(&self) -> Self::State {
+                    [<$name State>]::Tag(<$crate::interp_parser::DefaultInterp as $crate::interp_parser::ParserCommon<$tag>>::init(&$crate::interp_parser::DefaultInterp), None)
+                }
+            }
+
+            impl $crate::interp_parser::InterpParser<[<$name Schema>]> for $crate::interp_parser::DefaultInterp {
+                #[inline(never)]
+                fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> $crate::interp_parser::ParseResult<'a> {
+                    use $crate::interp_parser::{DefaultInterp, ParserCommon, InterpParser, OOB, set_from_thunk};
+                    let mut cursor = chunk;
+                    loop {
+                        match state {
+                            [<$name State>]::Tag(ref mut sub, ref mut tag) => {
+                                cursor = <DefaultInterp as InterpParser<$tag>>::parse(&DefaultInterp, sub, cursor, tag)?;
+                                match core::mem::take(tag) {
+                                    $(Some($($val)|+) => set_from_thunk(state, || [<$name State>]::$variant(<DefaultInterp as ParserCommon<$schema>>::init(&DefaultInterp), None)),)+
+                                    _ => return Err((Some(OOB::Reject), cursor)),
+                                }
+                            }
+                            $([<$name State>]::$variant(ref mut sub, ref mut sub_destination) => {
+                                cursor = <DefaultInterp as InterpParser<$schema>>::parse(&DefaultInterp, sub, cursor, sub_destination)?;
+                                *destination = Some($name::$variant(core::mem::take(sub_destination).ok_or((Some(OOB::Reject), cursor))?));
+                                return Ok(cursor);
+                            })+
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct ObserveBytes<X, F, S>(pub fn() -> X, pub F, pub S);
 
@@ -635,44 +2085,466 @@ impl<A, X:Clone, F: Fn(&mut X, &[u8])->(), S: InterpParser<A>> DynParser<A> for
         }
     }
 
-pub enum PairState<A, B> {
-    Init,
-    First(A),
-    Second(B),
+// Feeds the same input to both S and T, e.g. to render a field while also hashing it, and returns
+// both results. They must agree on where the value ends; if one accepts while the other still
+// wants more, or they accept at different points, we reject.
+#[derive(Clone)]
+pub struct Tee<S, T>(pub S, pub T);
+
+pub struct TeeState<SS, SR, TS, TR> {
+    seen : usize,
+    first : (SS, Option<SR>, Option<usize>),
+    second : (TS, Option<TR>, Option<usize>),
+}
+
+impl<A, S : ParserCommon<A>, T : ParserCommon<A>> ParserCommon<A> for Tee<S, T> {
+    type State = TeeState<<S as ParserCommon<A>>::State, <S as ParserCommon<A>>::Returning, <T as ParserCommon<A>>::State, <T as ParserCommon<A>>::Returning>;
+    type Returning = (<S as ParserCommon<A>>::Returning, <T as ParserCommon<A>>::Returning);
+    fn init(&self) -> Self::State {
+        TeeState {
+            seen: 0,
+            first: (self.0.init(), None, None),
+            second: (self.1.init(), None, None),
+        }
+    }
+}
+
+// Run one side of a Tee over the chunk, unless it has already accepted, recording where it ends.
+fn tee_step<'a, A, S : InterpParser<A>>(parser: &S, side: &mut (<S as ParserCommon<A>>::State, Option<<S as ParserCommon<A>>::Returning>, Option<usize>), seen: usize, chunk: &'a [u8]) -> Result<(), (PResult<OOB>, RemainingSlice<'a>)> {
+    if side.2.is_none() {
+        match parser.parse(&mut side.0, chunk, &mut side.1) {
+            Ok(rest) => { side.2 = Some(seen + chunk.len() - rest.len()); }
+            Err((None, _)) => { }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+impl<A, S : InterpParser<A>, T : InterpParser<A>> InterpParser<A> for Tee<S, T> {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        tee_step(&self.0, &mut state.first, state.seen, chunk)?;
+        tee_step(&self.1, &mut state.second, state.seen, chunk)?;
+        match (state.first.2, state.second.2) {
+            (None, None) => {
+                state.seen += chunk.len();
+                Err((None, &chunk[chunk.len()..]))
+            }
+            (Some(a), Some(b)) if a == b => {
+                let rest = &chunk[a - state.seen..];
+                let first = core::mem::take(&mut state.first.1).ok_or(rej(rest))?;
+                let second = core::mem::take(&mut state.second.1).ok_or(rej(rest))?;
+                *destination = Some((first, second));
+                Ok(rest)
+            }
+            (Some(a), _) | (_, Some(a)) => Err(rej(&chunk[core::cmp::min(a - state.seen, chunk.len())..])),
+        }
+    }
+}
+
+// Runs S and also returns the range of offsets it consumed, for highlighting the bytes of a field.
+// Offsets count from the base, which is zero unless set with init_param; to get positions relative
+// to the start of a message rather than of this field, pass the offset at which the field starts,
+// e.g. the end of the preceding field's span.
+#[derive(Clone)]
+pub struct Spanned<S>(pub S);
+
+pub struct SpannedState<State, R> {
+    base : usize,
+    consumed : usize,
+    child_state : State,
+    child_destination : Option<R>,
+}
+
+impl<A, S : ParserCommon<A>> ParserCommon<A> for Spanned<S> {
+    type State = SpannedState<<S as ParserCommon<A>>::State, <S as ParserCommon<A>>::Returning>;
+    type Returning = (<S as ParserCommon<A>>::Returning, core::ops::Range<usize>);
+    fn init(&self) -> Self::State {
+        SpannedState {
+            base: 0,
+            consumed: 0,
+            child_state: self.0.init(),
+            child_destination: None,
+        }
+    }
+}
+
+impl<A, S : InterpParser<A>> InterpParser<A> for Spanned<S> {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        let result = self.0.parse(&mut state.child_state, chunk, &mut state.child_destination);
+        if let Ok(new_cursor) | Err((None, new_cursor)) = result {
+            state.consumed += chunk.len() - new_cursor.len();
+        }
+        let new_cursor = result?;
+        let value = core::mem::take(&mut state.child_destination).ok_or(rej(new_cursor))?;
+        *destination = Some((value, state.base..state.base + state.consumed));
+        Ok(new_cursor)
+    }
+}
+
+impl<A, S : ParserCommon<A>> DynParser<A> for Spanned<S> {
+    type Parameter = usize;
+    #[inline(never)]
+    fn init_param(&self, param: Self::Parameter, state: &mut Self::State, destination: &mut Option<Self::Returning>) {
+        *destination = None;
+        set_from_thunk(state, || SpannedState {
+            base: param,
+            consumed: 0,
+            child_state: self.0.init(),
+            child_destination: None,
+        });
+    }
+}
+
+// For a field with the opposite byte order from the rest of the message: collects the bytes of a
+// fixed-width schema, reverses them, and runs S over the result. The buffer holds up to 8 bytes,
+// enough for any of the numbers; a wider schema fails to compile.
+#[derive(Clone)]
+pub struct ByteSwapped<S>(pub S);
+
+struct SwapWidth<P>(core::marker::PhantomData<P>);
+
+impl<P : FixedWidth> SwapWidth<P> {
+    const BYTES : usize = { assert!(P::BYTES <= 8, "ByteSwapped only handles schemas of up to 8 bytes"); P::BYTES };
+}
+
+impl<A : FixedWidth, S : ParserCommon<A>> ParserCommon<A> for ByteSwapped<S> {
+    type State = ArrayVec<u8, 8>;
+    type Returning = <S as ParserCommon<A>>::Returning;
+    fn init(&self) -> Self::State {
+        ArrayVec::new()
+    }
+}
+
+impl<A : FixedWidth, S : InterpParser<A>> InterpParser<A> for ByteSwapped<S> {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        let width = SwapWidth::<A>::BYTES;
+        let take = core::cmp::min(chunk.len(), width - state.len());
+        state.try_extend_from_slice(&chunk[0..take]).or(Err(rej(chunk)))?;
+        let cursor = &chunk[take..];
+        if state.len() < width {
+            return Err((None, cursor));
+        }
+        state.reverse();
+        let mut sub_state = self.0.init();
+        match self.0.parse(&mut sub_state, state, destination) {
+            Ok([]) => Ok(cursor),
+            _ => Err(rej(cursor)),
+        }
+    }
+}
+
+// Runs S and also keeps a verbatim copy of the bytes it consumed, e.g. to show a memo exactly as it
+// was sent. This is ObserveBytes with the observer fixed to copying into a buffer of N bytes; if S
+// consumes more than that, we reject.
+#[derive(Clone)]
+pub struct CaptureRaw<S, const N : usize>(pub S);
+
+pub struct CaptureRawState<State, R, const N : usize> {
+    child_state : State,
+    child_destination : Option<R>,
+    raw : ArrayVec<u8, N>,
+}
+
+impl<A, S : ParserCommon<A>, const N : usize> ParserCommon<A> for CaptureRaw<S, N> {
+    type State = CaptureRawState<<S as ParserCommon<A>>::State, <S as ParserCommon<A>>::Returning, N>;
+    type Returning = (<S as ParserCommon<A>>::Returning, ArrayVec<u8, N>);
+    fn init(&self) -> Self::State {
+        CaptureRawState {
+            child_state: self.0.init(),
+            child_destination: None,
+            raw: ArrayVec::new(),
+        }
+    }
+}
+
+impl<A, S : InterpParser<A>, const N : usize> InterpParser<A> for CaptureRaw<S, N> {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        let result = self.0.parse(&mut state.child_state, chunk, &mut state.child_destination);
+        let new_cursor = match result {
+            Ok(new_cursor) | Err((None, new_cursor)) => new_cursor,
+            Err(e) => return Err(e),
+        };
+        state.raw.try_extend_from_slice(&chunk[0..chunk.len()-new_cursor.len()]).or(Err(rej(chunk)))?;
+        result?;
+        let value = core::mem::take(&mut state.child_destination).ok_or(rej(new_cursor))?;
+        *destination = Some((value, core::mem::take(&mut state.raw)));
+        Ok(new_cursor)
+    }
+}
+
+#[derive(InPlaceInit)]
+#[repr(u8)]
+pub enum PairState<A, B> {
+    Init,
+    First(A),
+    Second(B),
+}
+
+impl<A : ParserCommon<C>, B : ParserCommon<D>, C, D> ParserCommon<(C, D)> for (A, B) {
+    type State = PairState<<A as ParserCommon<C>>::State, <B as ParserCommon<D>>::State>;
+    type Returning = (Option<A::Returning>, Option<B::Returning>);
+    // #[inline(never)] // Causes stack size increase
+    fn init(&self) -> Self::State {
+        PairState::Init
+    }
+    #[inline(never)]
+    fn init_in_place(&self, state: *mut core::mem::MaybeUninit<Self::State>) {
+        Self::State::init_init(state);
+    }
+}
+
+impl<A : InterpParser<C>, B : InterpParser<D>, C, D> InterpParser<(C, D)> for (A, B) {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        let mut cursor = chunk;
+        loop {
+            match state {
+                PairState::Init => {
+                    init_with_default(destination);
+                    set_from_thunk(state, || PairState::First(<A as ParserCommon<C>>::init(&self.0)));
+                }
+                PairState::First(ref mut sub) => {
+                    cursor = <A as InterpParser<C> >::parse(&self.0, sub, cursor, &mut destination.as_mut().ok_or(rej(cursor))?.0)?;
+                    set_from_thunk(state, || PairState::Second(<B as ParserCommon<D>>::init(&self.1)));
+                }
+                PairState::Second(ref mut sub) => {
+                    cursor = <B as InterpParser<D> >::parse(&self.1, sub, cursor, &mut destination.as_mut().ok_or(rej(cursor))?.1)?;
+                    break Ok(cursor);
+                }
+            }
+        }
+    }
+}
+
+// Tuples of three to eight parsers, parsing each element of the corresponding tuple schema in
+// turn. As with pairs, the result is a flat tuple of each element's Option result, and the state
+// holds only the element currently being parsed.
+macro_rules! seq {
+    { $state:ident $(($p:ident, $s:ident, $v:ident, $i:tt)),+ } => {
+        pub enum $state<$($p),+> {
+            Init,
+            $($v($p)),+
+        }
+
+        impl<$($p : ParserCommon<$s>, $s),+> ParserCommon<($($s),+)> for ($($p),+) {
+            type State = $state<$(<$p as ParserCommon<$s>>::State),+>;
+            type Returning = ($(Option<<$p as ParserCommon<$s>>::Returning>),+);
+            fn init(&self) -> Self::State {
+                $state::Init
+            }
+        }
+
+        impl<$($p : InterpParser<$s>, $s),+> InterpParser<($($s),+)> for ($($p),+) {
+            #[inline(never)]
+            fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+                let mut cursor = chunk;
+                loop {
+                    let next : usize = match state {
+                        $state::Init => {
+                            set_from_thunk(destination, || Some(($(None::<<$p as ParserCommon<$s>>::Returning>),+)));
+                            0
+                        }
+                        $($state::$v(ref mut sub) => {
+                            cursor = <$p as InterpParser<$s>>::parse(&self.$i, sub, cursor, &mut destination.as_mut().ok_or(rej(cursor))?.$i)?;
+                            $i + 1
+                        })+
+                    };
+                    match next {
+                        $($i => set_from_thunk(state, || $state::$v(<$p as ParserCommon<$s>>::init(&self.$i))),)+
+                        _ => break Ok(cursor),
+                    }
+                }
+            }
+        }
+    }
+}
+
+seq! { Seq3State (A0, S0, Elem0, 0), (A1, S1, Elem1, 1), (A2, S2, Elem2, 2) }
+seq! { Seq4State (A0, S0, Elem0, 0), (A1, S1, Elem1, 1), (A2, S2, Elem2, 2), (A3, S3, Elem3, 3) }
+seq! { Seq5State (A0, S0, Elem0, 0), (A1, S1, Elem1, 1), (A2, S2, Elem2, 2), (A3, S3, Elem3, 3), (A4, S4, Elem4, 4) }
+seq! { Seq6State (A0, S0, Elem0, 0), (A1, S1, Elem1, 1), (A2, S2, Elem2, 2), (A3, S3, Elem3, 3), (A4, S4, Elem4, 4), (A5, S5, Elem5, 5) }
+seq! { Seq7State (A0, S0, Elem0, 0), (A1, S1, Elem1, 1), (A2, S2, Elem2, 2), (A3, S3, Elem3, 3), (A4, S4, Elem4, 4), (A5, S5, Elem5, 5), (A6, S6, Elem6, 6) }
+seq! { Seq8State (A0, S0, Elem0, 0), (A1, S1, Elem1, 1), (A2, S2, Elem2, 2), (A3, S3, Elem3, 3), (A4, S4, Elem4, 4), (A5, S5, Elem5, 5), (A6, S6, Elem6, 6), (A7, S7, Elem7, 7) }
+
+// ByteDispatch reads a tag byte, maps it to a branch index with the first field, and parses the
+// rest of the value with that element of the tuple of parsers in the second field. This suits
+// opcode-style encodings with more than the two cases If handles, without needing a 256 entry
+// table. The result records which branch ran. A tag mapped to None, or to an index past the last
+// branch, rejects at the tag.
+#[derive(Clone)]
+pub struct ByteDispatch<T>(pub fn(u8) -> Option<usize>, pub T);
+
+macro_rules! byte_dispatch {
+    { $state:ident $result:ident $(($p:ident, $s:ident, $v:ident, $i:tt)),+ } => {
+        #[derive(Debug, PartialEq, Clone)]
+        pub enum $result<$($p),+> {
+            $($v($p)),+
+        }
+
+        pub enum $state<$($p),+> {
+            Tag,
+            $($v($p)),+
+        }
+
+        impl<$($p : ParserCommon<$s>, $s),+> ParserCommon<Dispatch<($($s),+)>> for ByteDispatch<($($p),+)> {
+            type State = $state<$((<$p as ParserCommon<$s>>::State, Option<<$p as ParserCommon<$s>>::Returning>)),+>;
+            type Returning = $result<$(<$p as ParserCommon<$s>>::Returning),+>;
+            fn init(&self) -> Self::State {
+                $state::Tag
+            }
+        }
+
+        impl<$($p : InterpParser<$s>, $s),+> InterpParser<Dispatch<($($s),+)>> for ByteDispatch<($($p),+)> {
+            #[inline(never)]
+            fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+                let mut cursor = chunk;
+                loop {
+                    match state {
+                        $state::Tag => {
+                            let (tag, rest) = cursor.split_first().ok_or((None, cursor))?;
+                            match (self.0)(*tag) {
+                                $(Some($i) => set_from_thunk(state, || $state::$v((<$p as ParserCommon<$s>>::init(&self.1.$i), None))),)+
+                                _ => break Err(rej(cursor)),
+                            }
+                            cursor = rest;
+                        }
+                        $($state::$v((ref mut sub, ref mut sub_destination)) => {
+                            cursor = <$p as InterpParser<$s>>::parse(&self.1.$i, sub, cursor, sub_destination)?;
+                            *destination = Some($result::$v(core::mem::take(sub_destination).ok_or(rej(cursor))?));
+                            break Ok(cursor);
+                        })+
+                    }
+                }
+            }
+        }
+    }
+}
+
+byte_dispatch! { Dispatch2State Dispatched2 (A0, S0, Branch0, 0), (A1, S1, Branch1, 1) }
+byte_dispatch! { Dispatch3State Dispatched3 (A0, S0, Branch0, 0), (A1, S1, Branch1, 1), (A2, S2, Branch2, 2) }
+byte_dispatch! { Dispatch4State Dispatched4 (A0, S0, Branch0, 0), (A1, S1, Branch1, 1), (A2, S2, Branch2, 2), (A3, S3, Branch3, 3) }
+
+// Matches the input against all of the candidate tags at once, dropping each as soon as it
+// diverges, and returns the index of the one that matched. If one candidate is a prefix of another
+// the shorter one wins, since we can't wait to see whether the longer one would have matched.
+#[derive(Clone)]
+pub struct OneOfTags<const N : usize>(pub [&'static [u8]; N]);
+
+pub struct OneOfTagsState<const N : usize> {
+    matched : usize,
+    alive : [bool; N],
 }
 
-impl<A : ParserCommon<C>, B : ParserCommon<D>, C, D> ParserCommon<(C, D)> for (A, B) {
-    type State = PairState<<A as ParserCommon<C>>::State, <B as ParserCommon<D>>::State>;
-    type Returning = (Option<A::Returning>, Option<B::Returning>);
-    // #[inline(never)] // Causes stack size increase
+impl<const N : usize> ParserCommon<Tags> for OneOfTags<N> {
+    type State = OneOfTagsState<N>;
+    type Returning = usize;
     fn init(&self) -> Self::State {
-        PairState::Init
+        OneOfTagsState { matched: 0, alive: [true; N] }
     }
 }
 
-impl<A : InterpParser<C>, B : InterpParser<D>, C, D> InterpParser<(C, D)> for (A, B) {
+impl<const N : usize> InterpParser<Tags> for OneOfTags<N> {
     #[inline(never)]
     fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
         let mut cursor = chunk;
         loop {
-            match state {
-                PairState::Init => {
-                    init_with_default(destination);
-                    set_from_thunk(state, || PairState::First(<A as ParserCommon<C>>::init(&self.0)));
-                }
-                PairState::First(ref mut sub) => {
-                    cursor = <A as InterpParser<C> >::parse(&self.0, sub, cursor, &mut destination.as_mut().ok_or(rej(cursor))?.0)?;
-                    set_from_thunk(state, || PairState::Second(<B as ParserCommon<D>>::init(&self.1)));
+            if let Some(i) = (0..N).find(|&i| state.alive[i] && self.0[i].len() == state.matched) {
+                *destination = Some(i);
+                return Ok(cursor);
+            }
+            let (byte, rest) = cursor.split_first().ok_or((None, cursor))?;
+            for i in 0..N {
+                if state.alive[i] && self.0[i][state.matched] != *byte {
+                    state.alive[i] = false;
                 }
-                PairState::Second(ref mut sub) => {
-                    cursor = <B as InterpParser<D> >::parse(&self.1, sub, cursor, &mut destination.as_mut().ok_or(rej(cursor))?.1)?;
-                    break Ok(cursor);
+            }
+            if !state.alive.iter().any(|a| *a) {
+                return Err(rej(cursor));
+            }
+            state.matched += 1;
+            cursor = rest;
+        }
+    }
+}
+
+// FlagFields interprets Flagged: T is a tuple of (bit, parser) pairs, one for each schema, and
+// field i is present when bit i.0 (0 being the least significant) of the flags byte is set. The
+// result has None for each absent field. Flags bits that no field claims are rejected. Each bit
+// must be less than 8; init panics otherwise, rather than leaving the field never present.
+#[derive(Clone)]
+pub struct FlagFields<T>(pub T);
+
+pub enum FlagFieldsState<T> {
+    Flags,
+    Next(u8, usize),
+    Field(u8, usize, T),
+}
+
+macro_rules! flag_fields {
+    { $state:ident $(($p:ident, $s:ident, $v:ident, $i:tt)),+ } => {
+        pub enum $state<$($p),+> {
+            $($v($p)),+
+        }
+
+        impl<$($p : ParserCommon<$s>, $s),+> ParserCommon<Flagged<($($s,)+)>> for FlagFields<($((u8, $p),)+)> {
+            type State = FlagFieldsState<$state<$(<$p as ParserCommon<$s>>::State),+>>;
+            type Returning = ($(Option<<$p as ParserCommon<$s>>::Returning>,)+);
+            fn init(&self) -> Self::State {
+                $(assert!((self.0).$i.0 < 8, "FlagFields: bit index out of range for a flags byte");)+
+                FlagFieldsState::Flags
+            }
+        }
+
+        impl<$($p : InterpParser<$s>, $s),+> InterpParser<Flagged<($($s,)+)>> for FlagFields<($((u8, $p),)+)> {
+            #[inline(never)]
+            fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+                let mut cursor = chunk;
+                loop {
+                    match state {
+                        FlagFieldsState::Flags => {
+                            let (flags, rest) = cursor.split_first().ok_or((None, cursor))?;
+                            let known = 0 $(| 1u8 << (self.0).$i.0)+;
+                            if flags & !known != 0 {
+                                break Err(rej(cursor));
+                            }
+                            *destination = Some(($(None::<<$p as ParserCommon<$s>>::Returning>,)+));
+                            let flags = *flags;
+                            set_from_thunk(state, || FlagFieldsState::Next(flags, 0));
+                            cursor = rest;
+                        }
+                        FlagFieldsState::Next(flags, index) => {
+                            let (flags, index) = (*flags, *index);
+                            match index {
+                                $($i => if flags & 1u8 << (self.0).$i.0 != 0 {
+                                    set_from_thunk(state, || FlagFieldsState::Field(flags, index, $state::$v(<$p as ParserCommon<$s>>::init(&(self.0).$i.1))));
+                                } else {
+                                    set_from_thunk(state, || FlagFieldsState::Next(flags, index + 1));
+                                })+
+                                _ => break Ok(cursor),
+                            }
+                        }
+                        $(FlagFieldsState::Field(flags, index, $state::$v(ref mut sub)) => {
+                            cursor = <$p as InterpParser<$s>>::parse(&(self.0).$i.1, sub, cursor, &mut destination.as_mut().ok_or(rej(cursor))?.$i)?;
+                            let (flags, index) = (*flags, *index);
+                            set_from_thunk(state, || FlagFieldsState::Next(flags, index + 1));
+                        })+
+                    }
                 }
             }
         }
     }
 }
 
+flag_fields! { FlagFields1State (A0, S0, Field0, 0) }
+flag_fields! { FlagFields2State (A0, S0, Field0, 0), (A1, S1, Field1, 1) }
+flag_fields! { FlagFields3State (A0, S0, Field0, 0), (A1, S1, Field1, 1), (A2, S2, Field2, 2) }
+flag_fields! { FlagFields4State (A0, S0, Field0, 0), (A1, S1, Field1, 1), (A2, S2, Field2, 2), (A3, S3, Field3, 3) }
+
 /*
  // TODO: handle struct-like data structures without using the pair parser above and with named
  // fields.
@@ -725,34 +2597,213 @@ impl<I, S : ParserCommon<I>> ParserCommon<I> for LengthLimited<S> {
             child_state: self.subparser.init()
         }
     }
+    // If the child has its own init_in_place, use it, so that we don't build the child's state on
+    // the stack just to copy it into ours.
+    #[inline(never)]
+    fn init_in_place(&self, state: *mut core::mem::MaybeUninit<Self::State>) {
+        call_fn(|| unsafe { core::ptr::addr_of_mut!((*(*state).as_mut_ptr()).bytes_seen).write(0) });
+        self.subparser.init_in_place(unsafe { core::ptr::addr_of_mut!((*(*state).as_mut_ptr()).child_state) as *mut core::mem::MaybeUninit<<S as ParserCommon<I>>::State> });
+    }
 }
 
 impl<I, S : InterpParser<I>> InterpParser<I> for LengthLimited<S> {
     // Start by typing out the type signature, copying the input slice into a mutable reference
     // and successfully return the cursor. Elaborate on the parser from there.
     fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
-        let feed_amount = core::cmp::min(chunk.len(), self.bytes_limit - state.bytes_seen);
-        // If you're calling a subparser, you will probably want to match on its status
-        // Note that we are trying to keep _our_ state in lockstep with the state of our child.
-        // If the child consumes, we account for it, even if we end up in a bad state.
-        match self.subparser.parse(&mut state.child_state, &chunk[0..feed_amount], destination) {
+        parse_length_limited(&self.subparser, self.bytes_limit, state, chunk, destination)
+    }
+}
+
+// Forwards the parameter to the subparser and starts counting again from zero; the limit stays the
+// one LengthLimited was built with. To choose the limit at runtime instead, use DynLengthLimited.
+impl<I, S : DynParser<I>> DynParser<I> for LengthLimited<S> {
+    type Parameter = <S as DynParser<I>>::Parameter;
+    #[inline(never)]
+    fn init_param(&self, param: Self::Parameter, state: &mut Self::State, destination: &mut Option<Self::Returning>) {
+        state.bytes_seen = 0;
+        self.subparser.init_param(param, &mut state.child_state, destination);
+    }
+}
+
+// Like LengthLimited, but the limit is the parameter, so that a length parsed upstream (e.g. by
+// DynBind) can bound the region S must fill exactly. Until init_param is called the limit is zero.
+#[derive(Clone)]
+pub struct DynLengthLimited<S>(pub S);
+
+pub struct DynLengthLimitedState<State> {
+    bytes_limit : usize,
+    inner : LengthLimitedState<State>,
+}
+
+impl<I, S : ParserCommon<I>> ParserCommon<I> for DynLengthLimited<S> {
+    type State = DynLengthLimitedState<<S as ParserCommon<I>>::State>;
+    type Returning = <S as ParserCommon<I>>::Returning;
+    fn init(&self) -> Self::State {
+        DynLengthLimitedState {
+            bytes_limit: 0,
+            inner: LengthLimitedState {
+                bytes_seen: 0,
+                child_state: self.0.init()
+            }
+        }
+    }
+}
+
+impl<I, S : InterpParser<I>> InterpParser<I> for DynLengthLimited<S> {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        parse_length_limited(&self.0, state.bytes_limit, &mut state.inner, chunk, destination)
+    }
+}
+
+impl<I, S : ParserCommon<I>> DynParser<I> for DynLengthLimited<S> {
+    type Parameter = usize;
+    #[inline(never)]
+    fn init_param(&self, param: Self::Parameter, state: &mut Self::State, destination: &mut Option<Self::Returning>) {
+        *destination = None;
+        state.bytes_limit = param;
+        state.inner.bytes_seen = 0;
+        set_from_thunk(&mut state.inner.child_state, || self.0.init());
+    }
+}
+
+// The body of LengthLimited's parse, taking the limit separately so that parsers which only learn
+// it partway through, like DerTlv, can share it.
+fn parse_length_limited<'a, I, S : InterpParser<I>>(subparser: &S, bytes_limit: usize, state: &mut LengthLimitedState<<S as ParserCommon<I>>::State>, chunk: &'a [u8], destination: &mut Option<<S as ParserCommon<I>>::Returning>) -> ParseResult<'a> {
+    let feed_amount = core::cmp::min(chunk.len(), bytes_limit - state.bytes_seen);
+    // If you're calling a subparser, you will probably want to match on its status
+    // Note that we are trying to keep _our_ state in lockstep with the state of our child.
+    // If the child consumes, we account for it, even if we end up in a bad state.
+    match subparser.parse(&mut state.child_state, &chunk[0..feed_amount], destination) {
+        Ok(new_cursor) => {
+            let consumed = feed_amount - new_cursor.len();
+            state.bytes_seen += consumed;
+            // If our child has accepted, they better have eaten all their vegetables.
+            if consumed < feed_amount || state.bytes_seen < bytes_limit {
+                return Err((Some (OOB::Reject), new_cursor));
+            }
+            Ok(&chunk[feed_amount..chunk.len()])
+        }
+        Err((None, new_cursor)) => {
+            let consumed = feed_amount - new_cursor.len();
+            state.bytes_seen += consumed;
+            // How can you have any pudding if you don't eat your meat?
+            if consumed < feed_amount || state.bytes_seen >= bytes_limit {
+                return Err((Some (OOB::Reject), new_cursor));
+            }
+            Err((None, new_cursor))
+        }
+        Err((w, new_cursor)) => {
+            let consumed = feed_amount - new_cursor.len();
+            state.bytes_seen += consumed;
+            Err((w, new_cursor))
+        }
+    }
+}
+
+// DerTlv parses an ASN.1 DER tag-length-value: a tag byte, a DerLength, and then a value of exactly
+// that many bytes with S, as LengthLimited would. The result is the tag and S's result. If the
+// first field gives an expected tag, any other tag is rejected; the whole byte is compared, so a
+// constructed encoding where a primitive one was expected, or vice versa, is rejected too.
+#[derive(Clone)]
+pub struct DerTlv<S>(pub Option<u8>, pub S);
+
+pub enum DerTlvState<IS, R> {
+    Tag,
+    Length(u8, DerLengthState),
+    Value(u8, usize, LengthLimitedState<IS>, Option<R>),
+}
+
+impl<I, S : ParserCommon<I>> ParserCommon<Tlv<I>> for DerTlv<S> {
+    type State = DerTlvState<<S as ParserCommon<I>>::State, <S as ParserCommon<I>>::Returning>;
+    type Returning = (u8, <S as ParserCommon<I>>::Returning);
+    fn init(&self) -> Self::State {
+        DerTlvState::Tag
+    }
+}
+
+impl<I, S : InterpParser<I>> InterpParser<Tlv<I>> for DerTlv<S> {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        use DerTlvState::*;
+        let mut cursor = chunk;
+        loop {
+            match state {
+                Tag => {
+                    let (tag, rest) = cursor.split_first().ok_or((None, cursor))?;
+                    match self.0 {
+                        Some(expected) if expected != *tag => { return Err(rej(cursor)); }
+                        _ => { }
+                    }
+                    let tag = *tag;
+                    cursor = rest;
+                    set_from_thunk(state, || Length(tag, <DefaultInterp as ParserCommon<DerLength>>::init(&DefaultInterp)));
+                }
+                Length(tag, ref mut nstate) => {
+                    let mut len = None;
+                    cursor = <DefaultInterp as InterpParser<DerLength>>::parse(&DefaultInterp, nstate, cursor, &mut len)?;
+                    let len = len.ok_or(rej(cursor))?;
+                    let tag = *tag;
+                    set_from_thunk(state, || Value(tag, len, LengthLimitedState { bytes_seen: 0, child_state: <S as ParserCommon<I>>::init(&self.1) }, None));
+                }
+                Value(tag, len, ref mut sub, ref mut sub_destination) => {
+                    cursor = parse_length_limited(&self.1, *len, sub, cursor, sub_destination)?;
+                    *destination = Some((*tag, core::mem::take(sub_destination).ok_or(rej(cursor))?));
+                    return Ok(cursor);
+                }
+            }
+        }
+    }
+}
+
+// Budgeted lets S read at most the number of bytes given by the first field. Unlike
+// LengthLimited, S need not use the whole budget, and running out of budget is not a rejection:
+// if S still wants more input once the budget is spent, we accept with Err(BudgetExceeded) and
+// leave the cursor just past the budget, so that the caller can e.g. show that the transaction
+// was too large to display rather than refusing it outright.
+#[derive(Clone)]
+pub struct Budgeted<S>(pub usize, pub S);
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BudgetExceeded;
+
+pub struct BudgetedState<State, R> {
+    bytes_seen : usize,
+    child_state : State,
+    child_destination : Option<R>,
+}
+
+impl<I, S : ParserCommon<I>> ParserCommon<I> for Budgeted<S> {
+    type State = BudgetedState<<S as ParserCommon<I>>::State, <S as ParserCommon<I>>::Returning>;
+    type Returning = Result<<S as ParserCommon<I>>::Returning, BudgetExceeded>;
+    fn init(&self) -> Self::State {
+        BudgetedState {
+            bytes_seen: 0,
+            child_state: self.1.init(),
+            child_destination: None,
+        }
+    }
+}
+
+impl<I, S : InterpParser<I>> InterpParser<I> for Budgeted<S> {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        let feed_amount = core::cmp::min(chunk.len(), self.0 - state.bytes_seen);
+        match self.1.parse(&mut state.child_state, &chunk[0..feed_amount], &mut state.child_destination) {
             Ok(new_cursor) => {
                 let consumed = feed_amount - new_cursor.len();
                 state.bytes_seen += consumed;
-                // If our child has accepted, they better have eaten all their vegetables.
-                if consumed < feed_amount || state.bytes_seen < self.bytes_limit {
-                    return Err((Some (OOB::Reject), new_cursor));
-                }
-                return Ok(&chunk[feed_amount..chunk.len()]);
+                *destination = Some(Ok(core::mem::take(&mut state.child_destination).ok_or(rej(new_cursor))?));
+                Ok(&chunk[consumed..])
             }
             Err((None, new_cursor)) => {
                 let consumed = feed_amount - new_cursor.len();
                 state.bytes_seen += consumed;
-                // How can you have any pudding if you don't eat your meat?
-                if consumed < feed_amount || state.bytes_seen >= self.bytes_limit {
-                    return Err((Some (OOB::Reject), new_cursor));
+                if state.bytes_seen >= self.0 {
+                    *destination = Some(Err(BudgetExceeded));
+                    return Ok(&chunk[consumed..]);
                 }
-                Err((None, new_cursor))
+                Err((None, &chunk[consumed..]))
             }
             Err((w, new_cursor)) => {
                 let consumed = feed_amount - new_cursor.len();
@@ -763,20 +2814,194 @@ impl<I, S : InterpParser<I>> InterpParser<I> for LengthLimited<S> {
     }
 }
 
+// Runs S and, once it accepts, swallows whatever is left of the chunk, so that parse_all and similar
+// drivers accept over-provisioned input. This is for fuzzing and differential testing against
+// other implementations only; it throws away input without looking at it, so it must not be used
+// anywhere that decides what gets signed.
+#[derive(Clone)]
+pub struct IgnoreTrailing<S>(pub S);
+
+impl<I, S : ParserCommon<I>> ParserCommon<I> for IgnoreTrailing<S> {
+    type State = <S as ParserCommon<I>>::State;
+    type Returning = <S as ParserCommon<I>>::Returning;
+    fn init(&self) -> Self::State {
+        self.0.init()
+    }
+}
+
+impl<I, S : InterpParser<I>> InterpParser<I> for IgnoreTrailing<S> {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        self.0.parse(state, chunk, destination)?;
+        Ok(&chunk[chunk.len()..])
+    }
+}
+
+// CRC-32 as used by zip, PNG and Ethernet, for use as the observer of ObserveBytes or Checksummed.
+// This is the bitwise form, to avoid spending a kilobyte on a lookup table.
+#[derive(Clone, Debug)]
+pub struct Crc32(u32);
+
+impl Crc32 {
+    pub fn new() -> Self {
+        Crc32(0xffff_ffff)
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u32;
+            for _ in 0..8 {
+                let mask = (self.0 & 1).wrapping_neg();
+                self.0 = (self.0 >> 1) ^ (0xedb8_8320 & mask);
+            }
+        }
+    }
+
+    pub fn finish(&self) -> u32 {
+        !self.0
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Checksummed parses the first half of a pair schema with S while feeding exactly the bytes S
+// consumes to the observer X, which starts from the first field and is updated by the second.
+// The second half of the pair is then read with DefaultInterp and must equal the checksum the
+// third field computes from X, or we reject. The trailer itself is not observed. The result is
+// S's result.
+#[derive(Clone)]
+pub struct Checksummed<X, F, G, S>(pub fn() -> X, pub F, pub G, pub S);
+
+pub enum ChecksummedState<X, IS, C, CS> {
+    Init,
+    Body(X, IS),
+    Trailer(C, CS, Option<C>),
+}
+
+impl<I, C, X, F : Fn(&mut X, &[u8]), G : Fn(&X) -> <DefaultInterp as ParserCommon<C>>::Returning, S : ParserCommon<I>> ParserCommon<(I, C)> for Checksummed<X, F, G, S> where
+    DefaultInterp : ParserCommon<C> {
+    type State = ChecksummedState<X, <S as ParserCommon<I>>::State, <DefaultInterp as ParserCommon<C>>::Returning, <DefaultInterp as ParserCommon<C>>::State>;
+    type Returning = <S as ParserCommon<I>>::Returning;
+    fn init(&self) -> Self::State {
+        ChecksummedState::Init
+    }
+}
+
+impl<I, C, X, F : Fn(&mut X, &[u8]), G : Fn(&X) -> <DefaultInterp as ParserCommon<C>>::Returning, S : InterpParser<I>> InterpParser<(I, C)> for Checksummed<X, F, G, S> where
+    DefaultInterp : InterpParser<C>,
+    <DefaultInterp as ParserCommon<C>>::Returning : PartialEq {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        use ChecksummedState::*;
+        let mut cursor = chunk;
+        loop {
+            match state {
+                Init => {
+                    set_from_thunk(state, || Body((self.0)(), <S as ParserCommon<I>>::init(&self.3)));
+                }
+                Body(ref mut observer, ref mut sub) => {
+                    match <S as InterpParser<I>>::parse(&self.3, sub, cursor, destination) {
+                        Ok(new_cursor) => {
+                            (self.1)(observer, &cursor[0..cursor.len()-new_cursor.len()]);
+                            cursor = new_cursor;
+                            let expected = (self.2)(observer);
+                            set_from_thunk(state, || Trailer(expected, <DefaultInterp as ParserCommon<C>>::init(&DefaultInterp), None));
+                        }
+                        Err((None, new_cursor)) => {
+                            (self.1)(observer, &cursor[0..cursor.len()-new_cursor.len()]);
+                            break Err((None, new_cursor));
+                        }
+                        Err(e) => break Err(e),
+                    }
+                }
+                Trailer(ref expected, ref mut sub, ref mut found) => {
+                    cursor = <DefaultInterp as InterpParser<C>>::parse(&DefaultInterp, sub, cursor, found)?;
+                    break match found {
+                        Some(ref f) if f == expected => Ok(cursor),
+                        _ => Err(rej(cursor)),
+                    };
+                }
+            }
+        }
+    }
+}
+
+// WithFooter parses the first half of a pair schema with S, and then the N bytes of the second
+// half, which must be exactly the given footer. We reject at the first byte that differs. The
+// result is S's result.
+#[derive(Clone)]
+pub struct WithFooter<S, const N : usize>(pub S, pub [u8; N]);
+
+pub enum WithFooterState<IS> {
+    Body(IS),
+    Footer(usize),
+}
+
+impl<I, S : ParserCommon<I>, const N : usize> ParserCommon<(I, Array<Byte, N>)> for WithFooter<S, N> {
+    type State = WithFooterState<<S as ParserCommon<I>>::State>;
+    type Returning = <S as ParserCommon<I>>::Returning;
+    fn init(&self) -> Self::State {
+        WithFooterState::Body(self.0.init())
+    }
+}
+
+impl<I, S : InterpParser<I>, const N : usize> InterpParser<(I, Array<Byte, N>)> for WithFooter<S, N> {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        use WithFooterState::*;
+        let mut cursor = chunk;
+        loop {
+            match state {
+                Body(ref mut sub) => {
+                    cursor = <S as InterpParser<I>>::parse(&self.0, sub, cursor, destination)?;
+                    set_from_thunk(state, || Footer(0));
+                }
+                Footer(ref mut matched) => {
+                    while *matched < N {
+                        let (byte, rest) = cursor.split_first().ok_or((None, cursor))?;
+                        if *byte != self.1[*matched] {
+                            return Err(rej(cursor));
+                        }
+                        *matched += 1;
+                        cursor = rest;
+                    }
+                    break Ok(cursor);
+                }
+            }
+        }
+    }
+}
+
 // I is a closure to initialize the observer of the input, namely X, which is usually a hasher
 // F is a method which does the observing for the observer.
 // S is the parser for the input of the hasher from the raw input
 // Note that ObserveLengthedBytes also consumes a length prefix from the raw input
 // Confer: LengthFallback
+//
+// The third element of the result says whether S accepted exactly the declared length, and the
+// first is S's result, which is only Some if it did; X sees the bytes either way.
 #[derive(Clone)]
 pub struct ObserveLengthedBytes<I : Fn () -> X, X, F, S>(pub I, pub F, pub S, pub bool);
 
+// How ObserveLengthedBytes dealt with the value: Parsed if S accepted after consuming exactly the
+// declared length, Skipped if S rejected, accepted early or wanted more, and the bytes were passed
+// to X without a result.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Lengthed {
+    Parsed,
+    Skipped,
+}
+
 impl<IFun : Fn () -> X, N, I, S : ParserCommon<I>, X, F: Fn(&mut X, &[u8])->()> ParserCommon<LengthFallback<N, I>> for ObserveLengthedBytes<IFun, X, F, S> where
     DefaultInterp : ParserCommon<N>,
     usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>,
     <DefaultInterp as ParserCommon<N>>::Returning: Copy {
     type State=LengthFallbackParserState<<DefaultInterp as ParserCommon<N>>::State, Option<<DefaultInterp as ParserCommon<N>>::Returning>, <S as ParserCommon<I>>::State>;
-    type Returning = (Option<<S as ParserCommon<I>>::Returning>, X);
+    type Returning = (Option<<S as ParserCommon<I>>::Returning>, X, Lengthed);
     // #[inline(never)] // Causes stack size increase
     fn init(&self) -> Self::State {
         LengthFallbackParserState::Length(<DefaultInterp as ParserCommon<N>>::init(&DefaultInterp), None)
@@ -799,16 +3024,16 @@ impl<IFun : Fn () -> X, N, I, S : InterpParser<I>, X, F: Fn(&mut X, &[u8])->()>
             break match state {
                 Length(ref mut nstate, ref mut length_out) => {
                     cursor = <DefaultInterp as InterpParser<N>>::parse(&DefaultInterp, nstate, cursor, length_out)?;
-                    let len = <usize as TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>>::try_from(length_out.ok_or(rej(cursor))?).or(Err(rej(cursor)))?;
+                    let len = <usize as TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>>::try_from(length_out.ok_or(rej(cursor))?).or(Err((Some(OOB::LengthOverflow), cursor)))?;
                     match destination {
                         None => {
                             call_me_maybe(|| {
                                 let result = self.0();
-                                *destination = Some((None, result));
+                                *destination = Some((None, result, Lengthed::Skipped));
                                 Some(())
                             }).ok_or(rej(cursor))?;
                         }
-                        _ => { }
+                        Some(ref mut d) => { d.2 = Lengthed::Skipped; }
                     }
                     set_from_thunk(state, || Element(0, len, <S as ParserCommon<I>>::init(&self.2)));
                     continue;
@@ -821,6 +3046,8 @@ impl<IFun : Fn () -> X, N, I, S : InterpParser<I>, X, F: Fn(&mut X, &[u8])->()>
                             *consumed += consumed_from_chunk;
                             self.1(&mut destination.as_mut().ok_or(rej(cursor))?.1, &cursor[0..passed_cursor.len()-new_cursor.len()]);
                             if *consumed == *len {
+                                destination.as_mut().ok_or(rej(cursor))?.2 = Lengthed::Parsed;
+                                set_from_thunk(state, || Done);
                                 Ok(&cursor[consumed_from_chunk..])
                             } else {
                                 cursor = new_cursor;
@@ -836,12 +3063,13 @@ impl<IFun : Fn () -> X, N, I, S : InterpParser<I>, X, F: Fn(&mut X, &[u8])->()>
                             *consumed += consumed_from_chunk;
                             self.1(&mut destination.as_mut().ok_or(rej(cursor))?.1, &cursor[0..passed_cursor.len()-new_cursor.len()]);
                             if *consumed == *len {
+                                set_from_thunk(&mut destination.as_mut().ok_or(rej(cursor))?.0, || None);
                                 Ok(&cursor[consumed_from_chunk..])
                             } else {
                                 Err((None, &cursor[consumed_from_chunk..]))
                             }
                         }
-                        Err((Some(OOB::Reject), _)) => {
+                        Err((Some(_), _)) => {
                             let cv = *consumed;
                             let lv = *len;
                             set_from_thunk(state, || Failed(cv, lv));
@@ -851,7 +3079,8 @@ impl<IFun : Fn () -> X, N, I, S : InterpParser<I>, X, F: Fn(&mut X, &[u8])->()>
                 }
                 Failed(ref mut consumed, len) => {
                     if self.3 {
-                        write!(DBG, "We hit a failed state in the parser\n").or(Err(rej(cursor)))?;
+                        #[cfg(feature = "logging")]
+                        error!("ObserveLengthedBytes: subparser rejected inside a length-prefixed value");
                         return Err((Some(OOB::Reject), cursor));
                     } else {
                         use core::cmp::min;
@@ -869,40 +3098,195 @@ impl<IFun : Fn () -> X, N, I, S : InterpParser<I>, X, F: Fn(&mut X, &[u8])->()>
                         }
                     }
                 }
-                Done => { Err((Some(OOB::Reject), cursor)) }
+                Done => { Err((Some(OOB::Reject), cursor)) }
+            }
+        }
+    }
+}
+
+impl<IFun : Fn () -> X, N, I, S : InterpParser<I>, X, F: Fn(&mut X, &[u8])->()> DynParser<LengthFallback<N, I>> for ObserveLengthedBytes<IFun, X, F, S> where
+    DefaultInterp : InterpParser<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>,
+    <DefaultInterp as ParserCommon<N>>::Returning: Copy {
+        type Parameter = X;
+        #[inline(never)]
+        fn init_param(&self, param: Self::Parameter, state: &mut Self::State, destination: &mut Option<Self::Returning>) {
+            set_from_thunk(destination, || { Some((None, param, Lengthed::Skipped)) });
+            *state = LengthFallbackParserState::Length(<DefaultInterp as ParserCommon<N>>::init(&DefaultInterp), None)
+        }
+    }
+
+// StrictLengthed parses a LengthFallback with S, but treats the length as authoritative instead of
+// falling back: S must accept after consuming exactly the declared number of bytes. If it accepts
+// early, or still wants more once the length is used up, we reject with OOB::LengthMismatch.
+// Rejections from S itself are passed through unchanged.
+#[derive(Clone)]
+pub struct StrictLengthed<S>(pub S);
+
+impl<N, I, S : ParserCommon<I>> ParserCommon<LengthFallback<N, I>> for StrictLengthed<S> where
+    DefaultInterp : ParserCommon<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>,
+    <DefaultInterp as ParserCommon<N>>::Returning: Copy {
+    type State=LengthFallbackParserState<<DefaultInterp as ParserCommon<N>>::State, Option<<DefaultInterp as ParserCommon<N>>::Returning>, <S as ParserCommon<I>>::State>;
+    type Returning = <S as ParserCommon<I>>::Returning;
+    fn init(&self) -> Self::State {
+        LengthFallbackParserState::Length(<DefaultInterp as ParserCommon<N>>::init(&DefaultInterp), None)
+    }
+    #[inline(never)]
+    fn init_in_place(&self, state: *mut core::mem::MaybeUninit<Self::State>) {
+        Self::State::init_length(state, |a| <DefaultInterp as ParserCommon<N>>::init_in_place(&DefaultInterp, a), |b| call_fn( || unsafe { (*b).as_mut_ptr().write(None); }));
+    }
+}
+
+impl<N, I, S : InterpParser<I>> InterpParser<LengthFallback<N, I>> for StrictLengthed<S> where
+    DefaultInterp : InterpParser<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>,
+    <DefaultInterp as ParserCommon<N>>::Returning: Copy {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        use LengthFallbackParserState::*;
+        let mut cursor : &'a [u8] = chunk;
+        loop {
+            break match state {
+                Length(ref mut nstate, ref mut length_out) => {
+                    cursor = <DefaultInterp as InterpParser<N>>::parse(&DefaultInterp, nstate, cursor, length_out)?;
+                    let len = <usize as TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>>::try_from(length_out.ok_or(rej(cursor))?).or(Err((Some(OOB::LengthOverflow), cursor)))?;
+                    set_from_thunk(state, || Element(0, len, <S as ParserCommon<I>>::init(&self.0)));
+                    continue;
+                }
+                Element(ref mut consumed, len, ref mut istate) => {
+                    let (result, _) = parse_strict_element(&self.0, consumed, *len, istate, cursor, destination);
+                    cursor = result?;
+                    set_from_thunk(state, || Done);
+                    Ok(cursor)
+                }
+                _ => { Err(rej(cursor)) }
             }
         }
     }
 }
 
-impl<IFun : Fn () -> X, N, I, S : InterpParser<I>, X, F: Fn(&mut X, &[u8])->()> DynParser<LengthFallback<N, I>> for ObserveLengthedBytes<IFun, X, F, S> where
+// The Element step of StrictLengthed and SignedEnvelope: runs S on at most the rest of the len
+// bytes, counting what it consumes in consumed, and rejects with LengthMismatch if S accepts early
+// or still wants more once len is used up. Also returns how many bytes of cursor S consumed, for
+// SignedEnvelope's observer.
+fn parse_strict_element<'a, I, S : InterpParser<I>>(sub: &S, consumed: &mut usize, len: usize, istate: &mut <S as ParserCommon<I>>::State, cursor: &'a [u8], destination: &mut Option<<S as ParserCommon<I>>::Returning>) -> (ParseResult<'a>, usize) {
+    let passed_cursor = &cursor[0..core::cmp::min(cursor.len(), len - *consumed)];
+    let result = sub.parse(istate, passed_cursor, destination);
+    let new_cursor = match result {
+        Ok(new_cursor) | Err((None, new_cursor)) => new_cursor,
+        Err(e) => return (Err(e), 0),
+    };
+    let consumed_from_chunk = passed_cursor.len() - new_cursor.len();
+    *consumed += consumed_from_chunk;
+    let rest = &cursor[consumed_from_chunk..];
+    (match result {
+        Ok(_) if *consumed == len => Ok(rest),
+        Err(_) if *consumed != len => Err((None, rest)),
+        _ => Err((Some(OOB::LengthMismatch), rest)),
+    }, consumed_from_chunk)
+}
+
+// SignedEnvelope is for sign-doc style messages, where we need both the parsed fields and the exact
+// bytes that get signed: it parses a LengthFallback strictly, as StrictLengthed does, and feeds
+// every byte of the value (not the length prefix) to the observer F, typically a hasher. The result
+// is S's result and the final observer. For a bounded verbatim copy of the value instead, wrap S in
+// CaptureRaw.
+#[derive(Clone)]
+pub struct SignedEnvelope<IFun : Fn () -> X, X, F, S>(pub IFun, pub F, pub S);
+
+impl<IFun : Fn () -> X, N, I, X, F : Fn(&mut X, &[u8]), S : ParserCommon<I>> ParserCommon<LengthFallback<N, I>> for SignedEnvelope<IFun, X, F, S> where
+    DefaultInterp : ParserCommon<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>,
+    <DefaultInterp as ParserCommon<N>>::Returning: Copy {
+    type State=LengthFallbackParserState<<DefaultInterp as ParserCommon<N>>::State, Option<<DefaultInterp as ParserCommon<N>>::Returning>, (<S as ParserCommon<I>>::State, Option<<S as ParserCommon<I>>::Returning>, X)>;
+    type Returning = (<S as ParserCommon<I>>::Returning, X);
+    fn init(&self) -> Self::State {
+        LengthFallbackParserState::Length(<DefaultInterp as ParserCommon<N>>::init(&DefaultInterp), None)
+    }
+}
+
+impl<IFun : Fn () -> X, N, I, X, F : Fn(&mut X, &[u8]), S : InterpParser<I>> InterpParser<LengthFallback<N, I>> for SignedEnvelope<IFun, X, F, S> where
     DefaultInterp : InterpParser<N>,
     usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>,
     <DefaultInterp as ParserCommon<N>>::Returning: Copy {
-        type Parameter = X;
-        #[inline(never)]
-        fn init_param(&self, param: Self::Parameter, state: &mut Self::State, destination: &mut Option<Self::Returning>) {
-            set_from_thunk(destination, || { Some((None, param)) });
-            *state = LengthFallbackParserState::Length(<DefaultInterp as ParserCommon<N>>::init(&DefaultInterp), None)
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        use LengthFallbackParserState::*;
+        let mut cursor : &'a [u8] = chunk;
+        loop {
+            break match state {
+                Length(ref mut nstate, ref mut length_out) => {
+                    cursor = <DefaultInterp as InterpParser<N>>::parse(&DefaultInterp, nstate, cursor, length_out)?;
+                    let len = <usize as TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>>::try_from(length_out.ok_or(rej(cursor))?).or(Err((Some(OOB::LengthOverflow), cursor)))?;
+                    set_from_thunk(state, || Element(0, len, (<S as ParserCommon<I>>::init(&self.2), None, (self.0)())));
+                    continue;
+                }
+                Element(ref mut consumed, len, (ref mut istate, ref mut sub_destination, ref mut observer)) => {
+                    let (result, consumed_from_chunk) = parse_strict_element(&self.2, consumed, *len, istate, cursor, sub_destination);
+                    (self.1)(observer, &cursor[0..consumed_from_chunk]);
+                    cursor = result?;
+                    let value = core::mem::take(sub_destination).ok_or(rej(cursor))?;
+                    let observed = match core::mem::replace(state, Done) {
+                        Element(_, _, (_, _, observed)) => observed,
+                        _ => return Err(rej(cursor)),
+                    };
+                    *destination = Some((value, observed));
+                    Ok(cursor)
+                }
+                _ => { Err(rej(cursor)) }
+            }
         }
     }
+}
 
-    pub struct DBG;
-    use core;
-    #[allow(unused_imports)]
-    use core::fmt::Write;
-    impl core::fmt::Write for DBG {
-        fn write_str(&mut self, s: &str) -> core::fmt::Result {
-            use arrayvec::ArrayString;
-            let mut qq = ArrayString::<128>::new();
-            qq.push_str(s);
-            #[cfg(target_os="nanos")]
-            nanos_sdk::debug_print(qq.as_str());
-            #[cfg(not(target_os="nanos"))]
-            std::print!("{}", qq.as_str());
-            Ok(())
+// For fixed-size material such as keys and signatures carried in a length-prefixed field: the
+// length must be exactly EXPECTED, and the value is read into [u8; EXPECTED]. Any other length is
+// rejected with LengthMismatch as soon as it is read, with the cursor at the start of the length
+// field (or of the part of it in the current chunk, if it was split).
+#[derive(Clone)]
+pub struct ExpectLength<const EXPECTED : usize>;
+
+impl<N, const EXPECTED : usize> ParserCommon<LengthFallback<N, Array<Byte, EXPECTED>>> for ExpectLength<EXPECTED> where
+    DefaultInterp : ParserCommon<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>,
+    <DefaultInterp as ParserCommon<N>>::Returning: Copy {
+    type State=LengthFallbackParserState<<DefaultInterp as ParserCommon<N>>::State, Option<<DefaultInterp as ParserCommon<N>>::Returning>, <DefaultInterp as ParserCommon<Array<Byte, EXPECTED>>>::State>;
+    type Returning = [u8; EXPECTED];
+    fn init(&self) -> Self::State {
+        LengthFallbackParserState::Length(<DefaultInterp as ParserCommon<N>>::init(&DefaultInterp), None)
+    }
+}
+
+impl<N, const EXPECTED : usize> InterpParser<LengthFallback<N, Array<Byte, EXPECTED>>> for ExpectLength<EXPECTED> where
+    DefaultInterp : InterpParser<N>,
+    usize: TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>,
+    <DefaultInterp as ParserCommon<N>>::Returning: Copy {
+    #[inline(never)]
+    fn parse<'a, 'b>(&self, state: &'b mut Self::State, chunk: &'a [u8], destination: &mut Option<Self::Returning>) -> ParseResult<'a> {
+        use LengthFallbackParserState::*;
+        let mut cursor : &'a [u8] = chunk;
+        loop {
+            break match state {
+                Length(ref mut nstate, ref mut length_out) => {
+                    let length_start = cursor;
+                    cursor = <DefaultInterp as InterpParser<N>>::parse(&DefaultInterp, nstate, cursor, length_out)?;
+                    if <usize as TryFrom<<DefaultInterp as ParserCommon<N>>::Returning>>::try_from(length_out.ok_or(rej(cursor))?).ok() != Some(EXPECTED) {
+                        return Err((Some(OOB::LengthMismatch), length_start));
+                    }
+                    set_from_thunk(state, || Element(0, EXPECTED, <DefaultInterp as ParserCommon<Array<Byte, EXPECTED>>>::init(&DefaultInterp)));
+                    continue;
+                }
+                Element(_, _, ref mut istate) => {
+                    cursor = <DefaultInterp as InterpParser<Array<Byte, EXPECTED>>>::parse(&DefaultInterp, istate, cursor, destination)?;
+                    set_from_thunk(state, || Done);
+                    Ok(cursor)
+                }
+                _ => { Err(rej(cursor)) }
+            }
         }
     }
+}
 
 /*
 #[cfg(test)]
@@ -1028,18 +3412,1025 @@ fn test_array() {
     parser_test_feed::<Array<Byte,5>, super::DefaultInterp, _>(p, &[b"foo",b"ba"], &b"fooba"[..], &[])
 }
 
-#[test]
-    fn test_darray() {
-        parser_test_feed::<DArray<Byte,Byte,5>, _, _>(SubInterp(DefaultInterp), &[b"\0"], &b""[..], &[]);
-        parser_test_feed::<DArray<Byte,Byte,5>, _, _>(SubInterp(DefaultInterp), &[b"\x05abcde"], &b"abcde"[..], &[]);
-        parser_test_feed::<DArray<Byte,Byte,5>, _, _>(SubInterp(Action(DefaultInterp, |_: &u8| Some(()))), &[b"\x05abcde"], &[(),(),(),(),()][..], &[]);
-        let obs = ObserveBytes(
-            || 0, |a : &mut usize,b : &[u8]| { *a += b.len(); },
-            SubInterp(
-                Action(DefaultInterp, |_: &u8| Some(()))));
-        parser_test_feed::<DArray<Byte,Byte,5>, _, _>(obs, &[b"\x05abcde"], &(6, (ArrayVec::from([(),(),(),(),()]))), &[]);
-    }
 */
 
 }
 */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+#[cfg(all(target_os="nanos", test))]
+    use testmacro::test_item as test;
+
+    #[test]
+    fn test_parse_all() {
+        assert_eq!(parse_all::<Byte, _>(&DefaultInterp, [&b"a"[..]]), Ok(b'a'));
+        assert_eq!(parse_all::<Array<Byte, 5>, _>(&DefaultInterp, [&b"foo"[..], &b""[..], &b"ba"[..]]), Ok(*b"fooba"));
+        assert_eq!(parse_all::<U32<{ Endianness::Little }>, _>(&DefaultInterp, [&b"\x01\x02"[..], &b"\x00\x00"[..]]), Ok(0x0201));
+        assert_eq!(parse_all::<DArray<Byte, Byte, 3>, _>(&SubInterp(DefaultInterp), [&b"\x03a"[..], &b"bc"[..]]), Ok(ArrayVec::from(*b"abc")));
+    }
+
+    #[test]
+    fn test_darray() {
+        // A single-byte length prefix, converted with usize: TryFrom<u8>.
+        type Format = DArray<Byte, Byte, 5>;
+        assert_eq!(parse_all::<Format, _>(&SubInterp(DefaultInterp), [&b"\0"[..]]), Ok(ArrayVec::new()));
+        assert_eq!(parse_all::<Format, _>(&SubInterp(DefaultInterp), [&b"\x05abcde"[..]]), Ok(ArrayVec::from(*b"abcde")));
+        assert_eq!(parse_all::<Format, _>(&SubInterp(DefaultInterp), [&b"\x05"[..], &b"ab"[..], &b"cde"[..]]), Ok(ArrayVec::from(*b"abcde")));
+        assert_eq!(parse_all::<Format, _>(&SubInterp(DefaultInterp), [&b"\x06abcdef"[..]]), Err(OOB::TooManyElements));
+        assert_eq!(parse_all::<Format, _>(&SubInterp(DefaultInterp), [&b"\x05abcd"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<Format, _>(&DefaultInterp, [&b"\x02ab"[..]]), Ok(b"ab".iter().copied().collect()));
+
+        let unit = || Action(DefaultInterp, (|_: &u8, d: &mut Option<()>| { *d = Some(()); Some(()) }) as fn(&u8, &mut Option<()>) -> Option<()>);
+        assert_eq!(parse_all::<Format, _>(&SubInterp(unit()), [&b"\x05abcde"[..]]), Ok(ArrayVec::from([(); 5])));
+        let obs = ObserveBytes(|| 0, |a: &mut usize, b: &[u8]| { *a += b.len(); }, SubInterp(unit()));
+        assert_eq!(parse_all::<Format, _>(&obs, [&b"\x05abcde"[..]]), Ok((6, Some(ArrayVec::from([(); 5])))));
+    }
+
+    #[test]
+    fn test_darray_rejected_element() {
+        type Format = DArray<Byte, Bcd<2, { BcdPadding::Ignore }>, 4>;
+        assert_eq!(parse_all::<Format, _>(&SubInterp(DefaultInterp), [&b"\x03\x12\x1a\x34"[..]]), Err(OOB::RejectedElement(1)));
+
+        // The rejection points at the start of the failing element.
+        let mut state = <SubInterp<DefaultInterp> as ParserCommon<Format>>::init(&SubInterp(DefaultInterp));
+        let mut destination = None;
+        assert_eq!(<SubInterp<DefaultInterp> as InterpParser<Format>>::parse(&SubInterp(DefaultInterp), &mut state, b"\x03\x12\x1a\x34", &mut destination), Err((Some(OOB::RejectedElement(1)), &b"\x1a\x34"[..])));
+        let mut state = <SubInterp<DefaultInterp> as ParserCommon<Format>>::init(&SubInterp(DefaultInterp));
+        assert_eq!(<SubInterp<DefaultInterp> as InterpParser<Format>>::parse(&SubInterp(DefaultInterp), &mut state, b"\x03\x12", &mut destination), Err((None, &b""[..])));
+        assert_eq!(<SubInterp<DefaultInterp> as InterpParser<Format>>::parse(&SubInterp(DefaultInterp), &mut state, b"\x34\xa1", &mut destination), Err((Some(OOB::RejectedElement(2)), &b"\xa1"[..])));
+
+        // More specific reasons from an element are passed through.
+        type Nested = DArray<Byte, DArray<Byte, Byte, 3>, 2>;
+        assert_eq!(parse_all::<Nested, _>(&SubInterp(DArrayUnique(DefaultInterp)), [&b"\x02\x01a\x02bb"[..]]), Err(OOB::Duplicate));
+        // Including the index reported by a nested array, which is the index in the inner one.
+        type NestedBcd = DArray<Byte, DArray<Byte, Bcd<2, { BcdPadding::Ignore }>, 3>, 2>;
+        assert_eq!(parse_all::<NestedBcd, _>(&SubInterp(SubInterp(DefaultInterp)), [&b"\x02\x01\x12\x02\x34\x1a"[..]]), Err(OOB::RejectedElement(1)));
+
+        // The other element loops report the index the same way.
+        let sum = || FoldInterp(DefaultInterp, || 0u64, |acc: &mut u64, x: u64| { *acc += x; });
+        assert_eq!(parse_all::<Format, _>(&DArraySorted(DefaultInterp), [&b"\x03\x12\x1a\x34"[..]]), Err(OOB::RejectedElement(1)));
+        assert_eq!(parse_all::<Format, _>(&SubInterpInPlace(DefaultInterp), [&b"\x03\x12\x34\x1a"[..]]), Err(OOB::RejectedElement(2)));
+        assert_eq!(parse_all::<DArray<Byte, ULEB128<u32, true>, 2>, _>(&DArrayTruncating(DefaultInterp), [&b"\x03\x01\x80\x00\x02"[..]]), Err(OOB::RejectedElement(1)));
+        assert_eq!(parse_all::<Format, _>(&sum(), [&b"\x03\x12\x1a\x34"[..]]), Err(OOB::RejectedElement(1)));
+        assert_eq!(parse_all::<Array<Bcd<2, { BcdPadding::Ignore }>, 3>, _>(&sum(), [&b"\x12\x34\x1a"[..]]), Err(OOB::RejectedElement(2)));
+
+        // Fixed-length arrays too.
+        type Fixed = Array<Bcd<2, { BcdPadding::Ignore }>, 3>;
+        assert_eq!(parse_all::<Fixed, _>(&SubInterp(DefaultInterp), [&b"\x12\x1a\x34"[..]]), Err(OOB::RejectedElement(1)));
+        let mut state = <SubInterp<DefaultInterp> as ParserCommon<Fixed>>::init(&SubInterp(DefaultInterp));
+        let mut destination = None;
+        assert_eq!(<SubInterp<DefaultInterp> as InterpParser<Fixed>>::parse(&SubInterp(DefaultInterp), &mut state, b"\x12\x1a\x34", &mut destination), Err((Some(OOB::RejectedElement(1)), &b"\x1a\x34"[..])));
+    }
+
+    #[test]
+    fn test_middle_endian() {
+        assert_eq!(parse_all::<U16<{ Endianness::Middle }>, _>(&DefaultInterp, [&b"\x0b\x0a"[..]]), Ok(0x0a0b));
+        assert_eq!(parse_all::<U32<{ Endianness::Middle }>, _>(&DefaultInterp, [&b"\x0b\x0a\x0d\x0c"[..]]), Ok(0x0a0b0c0d));
+        assert_eq!(parse_all::<U64<{ Endianness::Middle }>, _>(&DefaultInterp, [&b"\x02\x01\x04\x03\x06\x05\x08\x07"[..]]), Ok(0x0102030405060708));
+    }
+
+    #[test]
+    fn test_convert_byte_and_bool() {
+        use crate::endianness::TryConvert;
+        assert_eq!(<u8 as Convert<{ Endianness::Big }>>::deserialize([0xab]), 0xab);
+        assert_eq!(<u8 as Convert<{ Endianness::Little }>>::deserialize([0xab]), 0xab);
+        assert_eq!(<u8 as Convert<{ Endianness::Middle }>>::deserialize([0xab]), 0xab);
+        assert!(!<bool as Convert<{ Endianness::Middle }>>::deserialize([0]));
+        assert!(<bool as Convert<{ Endianness::Big }>>::deserialize([2]));
+        assert_eq!(<bool as TryConvert<{ Endianness::Big }>>::try_deserialize([0]), Some(false));
+        assert_eq!(<bool as TryConvert<{ Endianness::Little }>>::try_deserialize([1]), Some(true));
+        assert_eq!(<bool as TryConvert<{ Endianness::Middle }>>::try_deserialize([2]), None);
+        assert_eq!(<bool as TryConvert<{ Endianness::Big }>>::try_deserialize([0xff]), None);
+    }
+
+    #[test]
+    fn test_dyn_endian() {
+        assert_eq!(parse_all::<Array<Byte, 4>, _>(&DynEndianU32(Endianness::Big), [&b"\x01\x02\x03\x04"[..]]), Ok(0x01020304));
+        assert_eq!(parse_all::<Array<Byte, 4>, _>(&DynEndianU32(Endianness::Little), [&b"\x01\x02\x03\x04"[..]]), Ok(0x04030201));
+        let p = DynBind(
+            Action(DefaultInterp, (|flag: &u8, r: &mut Option<Endianness>| {
+                *r = Some(if *flag == 0 { Endianness::Big } else { Endianness::Little });
+                Some(())
+            }) as fn(&u8, &mut Option<Endianness>) -> Option<()>),
+            DynEndianU16(Endianness::Big));
+        assert_eq!(parse_all::<(Byte, Array<Byte, 2>), _>(&p, [&b"\x00\x01\x02"[..]]), Ok(0x0102));
+        assert_eq!(parse_all::<(Byte, Array<Byte, 2>), _>(&p, [&b"\x01\x01"[..], &b"\x02"[..]]), Ok(0x0201));
+    }
+
+    #[test]
+    fn test_bit_field() {
+        let msb = parse_all::<BitField<2, { BitOrder::MsbFirst }>, _>(&DefaultInterp, [&b"\xa0\x01"[..]]).unwrap();
+        assert!(msb.get(0));
+        assert!(!msb.get(1));
+        assert!(msb.get(2));
+        assert!(msb.get(15));
+        assert_eq!(msb.get_range(0, 3), 0b101);
+        assert_eq!(msb.get_range(2, 14), 0b10_0000_0000_0001);
+
+        let lsb = parse_all::<BitField<2, { BitOrder::LsbFirst }>, _>(&DefaultInterp, [&b"\x05\x80"[..]]).unwrap();
+        assert!(lsb.get(0));
+        assert!(!lsb.get(1));
+        assert!(lsb.get(2));
+        assert!(lsb.get(15));
+        assert_eq!(lsb.get_range(0, 3), 0b101);
+        assert_eq!(lsb.get_range(2, 14), 0b10_0000_0000_0001);
+    }
+
+    #[test]
+    #[should_panic(expected = "more than 64 bits")]
+    fn test_bit_field_range_too_wide() {
+        let bits : <BitField<9, { BitOrder::MsbFirst }> as RV>::R = Bits([0xff; 9]);
+        bits.get_range(0, 65);
+    }
+
+    #[test]
+    fn test_bcd() {
+        assert_eq!(parse_all::<Bcd<4, { BcdPadding::Ignore }>, _>(&DefaultInterp, [&b"\x12\x34"[..]]), Ok(1234));
+        assert_eq!(parse_all::<Bcd<6, { BcdPadding::Ignore }>, _>(&DefaultInterp, [&b"\x20"[..], &b"\x26\x10"[..]]), Ok(202610));
+        assert_eq!(parse_all::<Bcd<3, { BcdPadding::Ignore }>, _>(&DefaultInterp, [&b"\xf1\x23"[..]]), Ok(123));
+        assert_eq!(parse_all::<Bcd<3, { BcdPadding::Zero }>, _>(&DefaultInterp, [&b"\x01\x23"[..]]), Ok(123));
+        assert_eq!(parse_all::<Bcd<3, { BcdPadding::Zero }>, _>(&DefaultInterp, [&b"\xf1\x23"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<Bcd<4, { BcdPadding::Ignore }>, _>(&DefaultInterp, [&b"\x1a\x34"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<Bcd<4, { BcdPadding::Ignore }>, _>(&DefaultInterp, [&b"\x12\x3f"[..]]), Err(OOB::Reject));
+    }
+
+    #[test]
+    fn test_dyn_bind_chunk_boundaries() {
+        // Move from BindFirst to BindSecond with the first value split across chunks, at the very
+        // end of a chunk, and at the start of the next one, so that the state must survive the
+        // transition between calls.
+        let p = DynBind(
+            Action(DefaultInterp, (|flag: &u16, r: &mut Option<Endianness>| {
+                *r = Some(if *flag == 0 { Endianness::Big } else { Endianness::Little });
+                Some(())
+            }) as fn(&u16, &mut Option<Endianness>) -> Option<()>),
+            DynEndianU16(Endianness::Big));
+        type Format = (U16<{ Endianness::Big }>, Array<Byte, 2>);
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x00"[..], &b"\x01\x01\x02"[..]]), Ok(0x0201));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x00\x00"[..], &b"\x01\x02"[..]]), Ok(0x0102));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x00"[..], &b"\x00"[..], &b"\x01"[..], &b"\x02"[..]]), Ok(0x0102));
+
+        let mut state = <DynBind<_, _> as ParserCommon<Format>>::init(&p);
+        let mut destination = None;
+        assert_eq!(<DynBind<_, _> as InterpParser<Format>>::parse(&p, &mut state, b"\x00\x01", &mut destination), Err((None, &b""[..])));
+        assert!(matches!(state, DynBindState::BindSecond(_)));
+        assert_eq!(<DynBind<_, _> as InterpParser<Format>>::parse(&p, &mut state, b"\x01\x02", &mut destination), Ok(&b""[..]));
+        assert_eq!(destination, Some(0x0201));
+    }
+
+    #[test]
+    fn test_bind_eq() {
+        type Format = (U16<{ Endianness::Big }>, U16<{ Endianness::Big }>);
+        assert_eq!(parse_all::<Format, _>(&BindEq(DefaultInterp), [&b"\x01\x02\x01"[..], &b"\x02"[..]]), Ok(0x0102));
+        assert_eq!(parse_all::<Format, _>(&BindEq(DefaultInterp), [&b"\x01\x02\x01\x03"[..]]), Err(OOB::Reject));
+    }
+
+    #[test]
+    fn test_if() {
+        // A presence byte followed by a big-endian u16 only when the byte is nonzero.
+        let p = DynBind(
+            Action(DefaultInterp, (|flag: &u8, r: &mut Option<bool>| {
+                *r = Some(*flag != 0);
+                Some(())
+            }) as fn(&u8, &mut Option<bool>) -> Option<()>),
+            If(
+                Action(DefaultInterp, (|v: &u16, r: &mut Option<Option<u16>>| {
+                    *r = Some(Some(*v));
+                    Some(())
+                }) as fn(&u16, &mut Option<Option<u16>>) -> Option<()>),
+                Action(DefaultInterp, (|_: &[u8; 0], r: &mut Option<Option<u16>>| {
+                    *r = Some(None);
+                    Some(())
+                }) as fn(&[u8; 0], &mut Option<Option<u16>>) -> Option<()>)));
+        type Format = (Byte, Alt<U16<{ Endianness::Big }>, Array<Byte, 0>>);
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x01\x01\x02"[..]]), Ok(Some(0x0102)));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x00"[..]]), Ok(None));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x00\x01\x02"[..]]), Err(OOB::Reject));
+    }
+
+    #[test]
+    fn test_param_action() {
+        // The flag byte picks the byte order of the following u16 and is also reported alongside
+        // it, so both halves of the ParamAction need it.
+        let p = DynBind(
+            Action(DefaultInterp, (|flag: &u8, r: &mut Option<Endianness>| {
+                *r = Some(if *flag == 0 { Endianness::Big } else { Endianness::Little });
+                Some(())
+            }) as fn(&u8, &mut Option<Endianness>) -> Option<()>),
+            ParamAction(DynEndianU16(Endianness::Big), (|v: &u16, r: &mut Option<(Endianness, u16)>, e: Endianness| {
+                *r = Some((e, *v));
+                Some(())
+            }) as fn(&u16, &mut Option<(Endianness, u16)>, Endianness) -> Option<()>));
+        assert_eq!(parse_all::<(Byte, Array<Byte, 2>), _>(&p, [&b"\x00\x01\x02"[..]]), Ok((Endianness::Big, 0x0102)));
+        assert_eq!(parse_all::<(Byte, Array<Byte, 2>), _>(&p, [&b"\x01\x01"[..], &b"\x02"[..]]), Ok((Endianness::Little, 0x0201)));
+    }
+
+    #[test]
+    fn test_visit_action() {
+        // Emits the nonzero bytes of each value.
+        let nonzero = (|v: [u8; 3], sink: &mut dyn Sink<u8>| {
+            v.iter().filter(|b| **b != 0).try_for_each(|b| sink.put(*b))
+        }) as fn([u8; 3], &mut dyn Sink<u8>) -> Option<()>;
+        let p = VisitAction(DefaultInterp, ArrayVec::<u8, 2>::new, nonzero);
+        assert_eq!(parse_all::<Array<Byte, 3>, _>(&p, [&b"\x01\x00"[..], &b"\x02"[..]]), Ok(ArrayVec::from([1, 2])));
+        assert_eq!(parse_all::<Array<Byte, 3>, _>(&p, [&b"\x00\x00\x00"[..]]), Ok(ArrayVec::new()));
+        // The sink filling up rejects.
+        assert_eq!(parse_all::<Array<Byte, 3>, _>(&p, [&b"\x01\x02\x03"[..]]), Err(OOB::Reject));
+
+        // A sink that keeps nothing, but sees every item as it is emitted.
+        struct Count(usize);
+        impl Sink<u8> for Count {
+            fn put(&mut self, _item: u8) -> Option<()> {
+                self.0 += 1;
+                Some(())
+            }
+        }
+        let p = SubInterp(VisitAction(DefaultInterp, || Count(0), nonzero));
+        let mut state = <SubInterp<VisitAction<DefaultInterp, Count, fn([u8; 3], &mut dyn Sink<u8>) -> Option<()>>> as ParserCommon<Array<Array<Byte, 3>, 2>>>::init(&p);
+        let mut destination = None;
+        assert_eq!(<SubInterp<VisitAction<DefaultInterp, Count, fn([u8; 3], &mut dyn Sink<u8>) -> Option<()>>> as InterpParser<Array<Array<Byte, 3>, 2>>>::parse(&p, &mut state, b"\x01\x02\x03\x00\x05\x00", &mut destination), Ok(&b""[..]));
+        assert_eq!(destination.map(|counts| [counts[0].0, counts[1].0]), Some([3, 1]));
+    }
+
+    #[test]
+    fn test_param_select() {
+        // A header byte giving the width of the value that follows.
+        let p = DynBind(
+            Action(DefaultInterp, (|width: &u8, r: &mut Option<usize>| {
+                *r = Some(*width as usize);
+                Some(())
+            }) as fn(&u8, &mut Option<usize>) -> Option<()>),
+            ParamSelect((
+                Action(DefaultInterp, (|v: &u8, r: &mut Option<u32>| { *r = Some(*v as u32); Some(()) }) as fn(&u8, &mut Option<u32>) -> Option<()>),
+                Action(DefaultInterp, (|v: &u16, r: &mut Option<u32>| { *r = Some(*v as u32); Some(()) }) as fn(&u16, &mut Option<u32>) -> Option<()>),
+                DefaultInterp)));
+        type Format = (Byte, OneOf<(Byte, U16<{ Endianness::Big }>, U32<{ Endianness::Big }>)>);
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x00\x07"[..]]), Ok(7));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x01\x01"[..], &b"\x02"[..]]), Ok(0x0102));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x02\x01\x02\x03\x04"[..]]), Ok(0x01020304));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x03\x01"[..]]), Err(OOB::Reject));
+    }
+
+    #[test]
+    fn test_in_range() {
+        let p = InRange(DefaultInterp, 10u16, 20u16);
+        assert_eq!(parse_all::<U16<{ Endianness::Little }>, _>(&p, [&b"\x0a"[..], &b"\x00"[..]]), Ok(10));
+        assert_eq!(parse_all::<U16<{ Endianness::Little }>, _>(&p, [&b"\x0f\x00"[..]]), Ok(15));
+        assert_eq!(parse_all::<U16<{ Endianness::Little }>, _>(&p, [&b"\x14\x00"[..]]), Ok(20));
+        assert_eq!(parse_all::<U16<{ Endianness::Little }>, _>(&p, [&b"\x09\x00"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<U16<{ Endianness::Little }>, _>(&p, [&b"\x15\x00"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<U16<{ Endianness::Little }>, _>(&p, [&b"\x0a\x01"[..]]), Err(OOB::Reject));
+    }
+
+    #[test]
+    fn test_pair_init_in_place() {
+        type P = (DefaultInterp, DefaultInterp);
+        let p = (DefaultInterp, DefaultInterp);
+        let mut state = core::mem::MaybeUninit::<<P as ParserCommon<(Byte, Byte)>>::State>::uninit();
+        <P as ParserCommon<(Byte, Byte)>>::init_in_place(&p, &mut state);
+        let mut state = unsafe { state.assume_init() };
+        assert!(matches!(state, PairState::Init));
+        let mut destination = None;
+        assert_eq!(<P as InterpParser<(Byte, Byte)>>::parse(&p, &mut state, b"ab", &mut destination), Ok(&b""[..]));
+        assert_eq!(destination, Some((Some(b'a'), Some(b'b'))));
+    }
+
+    #[test]
+    fn test_length_limited_init_in_place() {
+        let p = LengthLimited { bytes_limit: 3, subparser: (DefaultInterp, DefaultInterp) };
+        type S = <LengthLimited<(DefaultInterp, DefaultInterp)> as ParserCommon<(Byte, Array<Byte, 2>)>>::State;
+        let mut state = core::mem::MaybeUninit::<S>::uninit();
+        <LengthLimited<(DefaultInterp, DefaultInterp)> as ParserCommon<(Byte, Array<Byte, 2>)>>::init_in_place(&p, &mut state);
+        let mut state = unsafe { state.assume_init() };
+        assert_eq!(state.bytes_seen, 0);
+        assert!(matches!(state.child_state, PairState::Init));
+        let mut destination = None;
+        assert_eq!(<LengthLimited<(DefaultInterp, DefaultInterp)> as InterpParser<(Byte, Array<Byte, 2>)>>::parse(&p, &mut state, b"abcd", &mut destination), Ok(&b"d"[..]));
+        assert_eq!(destination, Some((Some(b'a'), Some(*b"bc"))));
+    }
+
+    #[test]
+    fn test_length_limited_dyn() {
+        // The parameter goes to the subparser, and the count restarts.
+        type Format = Array<Byte, 2>;
+        let p = LengthLimited { bytes_limit: 2, subparser: ObserveBytes(|| 0usize, |n: &mut usize, b: &[u8]| { *n += b.len(); }, DefaultInterp) };
+        let mut state = <_ as ParserCommon<Format>>::init(&p);
+        let mut destination = None;
+        <_ as DynParser<Format>>::init_param(&p, 10, &mut state, &mut destination);
+        assert_eq!(<_ as InterpParser<Format>>::parse(&p, &mut state, b"abc", &mut destination), Ok(&b"c"[..]));
+        assert_eq!(destination, Some((12, Some(*b"ab"))));
+        <_ as DynParser<Format>>::init_param(&p, 0, &mut state, &mut destination);
+        assert_eq!(<_ as InterpParser<Format>>::parse(&p, &mut state, b"de", &mut destination), Ok(&b""[..]));
+        assert_eq!(destination, Some((2, Some(*b"de"))));
+    }
+
+    #[test]
+    fn test_dyn_length_limited() {
+        type Format = DArray<Byte, Byte, 8>;
+        let p = DynLengthLimited(SubInterp(DefaultInterp));
+        let mut state = <_ as ParserCommon<Format>>::init(&p);
+        let mut destination = None;
+        <_ as DynParser<Format>>::init_param(&p, 3, &mut state, &mut destination);
+        assert_eq!(<_ as InterpParser<Format>>::parse(&p, &mut state, b"\x02a", &mut destination), Err((None, &b""[..])));
+        assert_eq!(<_ as InterpParser<Format>>::parse(&p, &mut state, b"bc", &mut destination), Ok(&b"c"[..]));
+        assert_eq!(destination, Some(b"ab".iter().copied().collect::<ArrayVec<u8, 8>>()));
+
+        // The region must be filled exactly.
+        <_ as DynParser<Format>>::init_param(&p, 4, &mut state, &mut destination);
+        assert_eq!(<_ as InterpParser<Format>>::parse(&p, &mut state, b"\x02abc", &mut destination), Err((Some(OOB::Reject), &b"c"[..])));
+        <_ as DynParser<Format>>::init_param(&p, 2, &mut state, &mut destination);
+        assert_eq!(<_ as InterpParser<Format>>::parse(&p, &mut state, b"\x02abc", &mut destination), Err((Some(OOB::Reject), &b""[..])));
+    }
+
+    #[test]
+    fn test_drop_number_resumes() {
+        // The skip count survives between calls, so a number split across chunks is skipped
+        // exactly once rather than restarting at each chunk.
+        let one_byte_chunks = [&b"\x01"[..], &b"\x02"[..], &b"\x03"[..], &b"\x04"[..], &b"\x05"[..], &b"\x06"[..], &b"\x07"[..], &b"\x08"[..]];
+        assert_eq!(parse_all::<U64<{ Endianness::Big }>, _>(&DropInterp, one_byte_chunks), Ok(()));
+        assert_eq!(parse_all::<U64<{ Endianness::Big }>, _>(&DropInterp, one_byte_chunks[..7].iter().copied()), Err(OOB::Reject));
+        assert_eq!(parse_all::<U64<{ Endianness::Big }>, _>(&DropInterp, one_byte_chunks.iter().copied().chain([&b"\x09"[..]])), Err(OOB::Reject));
+        assert_eq!(parse_all::<U64<{ Endianness::Big }>, _>(&DefaultInterp, one_byte_chunks), Ok(0x0102030405060708));
+        assert_eq!(parse_all::<U16<{ Endianness::Little }>, _>(&DropInterp, one_byte_chunks[..2].iter().copied()), Ok(()));
+        assert_eq!(parse_all::<U16<{ Endianness::Little }>, _>(&DropInterp, one_byte_chunks[..3].iter().copied()), Err(OOB::Reject));
+        assert_eq!(parse_all::<U24<{ Endianness::Big }>, _>(&DropInterp, [&b"\x01\x02"[..], &b"\x03"[..]]), Ok(()));
+        assert_eq!(parse_all::<U32<{ Endianness::Big }>, _>(&DropInterp, [&b"\x01"[..], &b"\x02\x03"[..], &b"\x04"[..]]), Ok(()));
+
+        let mut state = <DropInterp as ParserCommon<U32<{ Endianness::Big }>>>::init(&DropInterp);
+        assert_eq!(<DropInterp as InterpParser<U32<{ Endianness::Big }>>>::parse(&DropInterp, &mut state, b"\x01\x02", &mut None), Err((None, &b""[..])));
+        assert_eq!(<DropInterp as InterpParser<U32<{ Endianness::Big }>>>::parse(&DropInterp, &mut state, b"\x03\x04\x05", &mut None), Ok(&b"\x05"[..]));
+    }
+
+    #[test]
+    fn test_darray_truncating() {
+        type Format = DArray<Byte, U16<{ Endianness::Big }>, 2>;
+        assert_eq!(parse_all::<Format, _>(&DArrayTruncating(DefaultInterp), [&b"\x01\x00\x01"[..]]), Ok([1].iter().copied().collect::<ArrayVec<u16, 2>>()));
+        assert_eq!(parse_all::<Format, _>(&DArrayTruncating(DefaultInterp), [&b"\x02\x00\x01\x00\x02"[..]]), Ok(ArrayVec::from([1, 2])));
+        assert_eq!(parse_all::<Format, _>(&DArrayTruncating(DefaultInterp), [&b"\x04\x00\x01\x00"[..], &b"\x02\x00\x03\x00"[..], &b"\x04"[..]]), Ok(ArrayVec::from([1, 2])));
+        // The skipped elements still have to be present in full.
+        assert_eq!(parse_all::<Format, _>(&DArrayTruncating(DefaultInterp), [&b"\x04\x00\x01\x00\x02\x00\x03\x00"[..]]), Err(OOB::Reject));
+    }
+
+    #[test]
+    fn test_move_action_at_start() {
+        let p = (DefaultInterp, MoveActionAtStart(DefaultInterp, (|v: u8, r: &mut Option<u8>| {
+            if v > 9 { return None; }
+            *r = Some(v);
+            Some(())
+        }) as fn(u8, &mut Option<u8>) -> Option<()>));
+        type P = (DefaultInterp, MoveActionAtStart<DefaultInterp, fn(u8, &mut Option<u8>) -> Option<()>>);
+        let mut state = <P as ParserCommon<(Byte, Byte)>>::init(&p);
+        let mut destination = None;
+        assert_eq!(<P as InterpParser<(Byte, Byte)>>::parse(&p, &mut state, b"\x01\x0a\x02", &mut destination), Err((Some(OOB::Reject), &b"\x0a\x02"[..])));
+
+        let mut state = <P as ParserCommon<(Byte, Byte)>>::init(&p);
+        let mut destination = None;
+        assert_eq!(<P as InterpParser<(Byte, Byte)>>::parse(&p, &mut state, b"\x01\x09\x02", &mut destination), Ok(&b"\x02"[..]));
+        assert_eq!(destination, Some((Some(1), Some(9))));
+    }
+
+    #[test]
+    fn test_u24() {
+        assert_eq!(parse_all::<U24<{ Endianness::Big }>, _>(&DefaultInterp, [&b"\x01\x02"[..], &b"\x03"[..]]), Ok(0x010203));
+        assert_eq!(parse_all::<U24<{ Endianness::Little }>, _>(&DefaultInterp, [&b"\x01\x02\x03"[..]]), Ok(0x030201));
+        assert_eq!(parse_all::<U24<{ Endianness::Little }>, _>(&DefaultInterp, [&b"\xff\xff\xff"[..]]), Ok(0x00ffffff));
+        assert_eq!(parse_all::<U24<{ Endianness::Little }>, _>(&DropInterp, [&b"\xff\xff\xff"[..]]), Ok(()));
+        assert_eq!(parse_all::<DArray<U24<{ Endianness::Little }>, Byte, 3>, _>(&SubInterp(DefaultInterp), [&b"\x03\x00\x00abc"[..]]), Ok(ArrayVec::from(*b"abc")));
+    }
+
+    #[test]
+    fn test_darray_length_overflow() {
+        // 2^32 doesn't fit in usize on 32-bit targets, so the length conversion fails there; on
+        // wider targets the length converts but exceeds the capacity. Either way we reject right
+        // after the length, without reading any elements.
+        type Format = DArray<U64<{ Endianness::Big }>, Byte, 4>;
+        #[cfg(target_pointer_width = "32")]
+        let expected = OOB::LengthOverflow;
+        #[cfg(not(target_pointer_width = "32"))]
+        let expected = OOB::TooManyElements;
+        let mut state = <SubInterp<DefaultInterp> as ParserCommon<Format>>::init(&SubInterp(DefaultInterp));
+        let mut destination = None;
+        let rv = <SubInterp<DefaultInterp> as InterpParser<Format>>::parse(&SubInterp(DefaultInterp), &mut state, b"\x00\x00\x00\x01\x00\x00\x00\x00abcd", &mut destination);
+        assert_eq!(rv, Err((Some(expected), &b"abcd"[..])));
+
+        // A u128 length of 2^64 overflows usize on this host as well.
+        type Wide = DArray<ULEB128<u128, true>, Byte, 4>;
+        assert_eq!(parse_all::<Wide, _>(&SubInterp(DefaultInterp), [&b"\x80\x80\x80\x80\x80\x80\x80\x80\x80\x02abcd"[..]]), Err(OOB::LengthOverflow));
+        assert_eq!(parse_all::<Wide, _>(&SubInterpInPlace(DefaultInterp), [&b"\x80\x80\x80\x80\x80\x80\x80\x80\x80\x02abcd"[..]]), Err(OOB::LengthOverflow));
+        assert_eq!(parse_all::<Wide, _>(&FoldInterp(DefaultInterp, || 0, |n: &mut usize, _: u8| { *n += 1; }), [&b"\x80\x80\x80\x80\x80\x80\x80\x80\x80\x02abcd"[..]]), Err(OOB::LengthOverflow));
+    }
+
+    #[test]
+    fn test_sub_interp_in_place() {
+        type Format = DArray<Byte, U16<{ Endianness::Big }>, 3>;
+        assert_eq!(parse_all::<Format, _>(&SubInterpInPlace(DefaultInterp), [&b"\x00"[..]]), Ok(ArrayVec::new()));
+        assert_eq!(parse_all::<Format, _>(&SubInterpInPlace(DefaultInterp), [&b"\x02\x00"[..], &b"\x01\x00\x02"[..]]), Ok([1, 2].iter().copied().collect::<ArrayVec<u16, 3>>()));
+        assert_eq!(parse_all::<Format, _>(&SubInterpInPlace(DefaultInterp), [&b"\x04\x00\x01\x00\x02\x00\x03\x00\x04"[..]]), Err(OOB::TooManyElements));
+
+        // Elements land in the destination as they are parsed.
+        let mut state = <SubInterpInPlace<DefaultInterp> as ParserCommon<Format>>::init(&SubInterpInPlace(DefaultInterp));
+        let mut destination = None;
+        assert_eq!(<SubInterpInPlace<DefaultInterp> as InterpParser<Format>>::parse(&SubInterpInPlace(DefaultInterp), &mut state, b"\x02\x00\x01\x00", &mut destination), Err((None, &b""[..])));
+        assert_eq!(destination.as_ref().map(|v| &v[..]), Some(&[1][..]));
+    }
+
+    #[test]
+    fn test_seq() {
+        type Format = (Byte, U16<{ Endianness::Big }>, Array<Byte, 2>);
+        let p = (DefaultInterp, DefaultInterp, DefaultInterp);
+        assert_eq!(parse_all::<Format, _>(&p, [&b"a\x01"[..], &b"\x02b"[..], &b"c"[..]]), Ok((Some(b'a'), Some(0x0102), Some(*b"bc"))));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"a\x01\x02b"[..]]), Err(OOB::Reject));
+
+        type Format8 = (Byte, Byte, Byte, Byte, Byte, Byte, Byte, U16<{ Endianness::Little }>);
+        let p8 = (DefaultInterp, DropInterp, DefaultInterp, DropInterp, DefaultInterp, DropInterp, DefaultInterp, DefaultInterp);
+        assert_eq!(parse_all::<Format8, _>(&p8, [&b"abcdefg\x01\x02"[..]]),
+            Ok((Some(b'a'), Some(()), Some(b'c'), Some(()), Some(b'e'), Some(()), Some(b'g'), Some(0x0201))));
+    }
+
+    #[test]
+    fn test_zero_length() {
+        // Zero-length schemas accept immediately without consuming anything, even with no input.
+        assert_eq!(parse_all::<Array<Byte, 0>, _>(&DefaultInterp, [&b""[..]]), Ok([]));
+        assert_eq!(parse_all::<Array<Byte, 0>, _>(&SubInterp(DropInterp), [&b""[..]]), Ok([]));
+        assert_eq!(parse_all::<Array<U32<{ Endianness::Big }>, 0>, _>(&DefaultInterp, [&b""[..]]), Ok([]));
+        assert_eq!(parse_all::<DArray<Byte, Byte, 0>, _>(&SubInterp(DefaultInterp), [&b"\x00"[..]]), Ok(ArrayVec::new()));
+        assert_eq!(parse_all::<DArray<Byte, Byte, 0>, _>(&SubInterp(DefaultInterp), [&b"\x01a"[..]]), Err(OOB::TooManyElements));
+        assert_eq!(parse_all::<Bcd<0, { BcdPadding::Ignore }>, _>(&DefaultInterp, [&b""[..]]), Ok(0));
+        assert_eq!(parse_all::<BitField<0, { BitOrder::MsbFirst }>, _>(&DefaultInterp, [&b""[..]]), Ok(Bits([])));
+
+        let mut state = <DefaultInterp as ParserCommon<Array<Byte, 0>>>::init(&DefaultInterp);
+        let mut destination = None;
+        assert_eq!(<DefaultInterp as InterpParser<Array<Byte, 0>>>::parse(&DefaultInterp, &mut state, b"abc", &mut destination), Ok(&b"abc"[..]));
+        assert_eq!(destination, Some([]));
+    }
+
+    #[test]
+    fn test_fold_interp() {
+        let sum = FoldInterp(DefaultInterp, || 0u32, |acc: &mut u32, x: u16| { *acc += x as u32; });
+        assert_eq!(parse_all::<Array<U16<{ Endianness::Big }>, 3>, _>(&sum, [&b"\x00\x01\x00"[..], &b"\x02\x01\x00"[..]]), Ok(0x103));
+        assert_eq!(parse_all::<DArray<Byte, U16<{ Endianness::Big }>, 3>, _>(&sum, [&b"\x03\x00\x01\x00\x02"[..], &b"\x01\x00"[..]]), Ok(0x103));
+        assert_eq!(parse_all::<DArray<Byte, U16<{ Endianness::Big }>, 3>, _>(&sum, [&b"\x00"[..]]), Ok(0));
+        // The capacity is enforced even though no elements are stored.
+        assert_eq!(parse_all::<DArray<Byte, U16<{ Endianness::Big }>, 2>, _>(&sum, [&b"\x03\x00\x01\x00\x02\x01\x00"[..]]), Err(OOB::TooManyElements));
+
+        let max = FoldInterp(DefaultInterp, || 0u8, |acc: &mut u8, x: u8| { *acc = core::cmp::max(*acc, x); });
+        assert_eq!(parse_all::<DArray<Byte, Byte, 4>, _>(&max, [&b"\x04\x03\x09\x01\x07"[..]]), Ok(9));
+
+        // A destination left over from an earlier parse doesn't leak into the result.
+        type Format = Array<U16<{ Endianness::Big }>, 2>;
+        let mut state = <FoldInterp<_, _, _> as ParserCommon<Format>>::init(&sum);
+        let mut destination = Some(0x1000);
+        assert_eq!(<FoldInterp<_, _, _> as InterpParser<Format>>::parse(&sum, &mut state, b"\x00\x01\x00", &mut destination), Err((None, &b""[..])));
+        assert_eq!(<FoldInterp<_, _, _> as InterpParser<Format>>::parse(&sum, &mut state, b"\x02", &mut destination), Ok(&b""[..]));
+        assert_eq!(destination, Some(3));
+    }
+
+    #[test]
+    fn test_darray_sorted() {
+        type Format = DArray<Byte, U16<{ Endianness::Big }>, 4>;
+        assert_eq!(parse_all::<Format, _>(&DArraySorted(DefaultInterp), [&b"\x03\x00\x01"[..], &b"\x00\x01\x01\x00"[..]]), Ok([1, 1, 0x100].iter().copied().collect::<ArrayVec<u16, 4>>()));
+        assert_eq!(parse_all::<Format, _>(&DArraySorted(DefaultInterp), [&b"\x00"[..]]), Ok(ArrayVec::new()));
+
+        // Rejects at the first inversion, without reading further elements.
+        let mut state = <DArraySorted<DefaultInterp> as ParserCommon<Format>>::init(&DArraySorted(DefaultInterp));
+        let mut destination = None;
+        assert_eq!(<DArraySorted<DefaultInterp> as InterpParser<Format>>::parse(&DArraySorted(DefaultInterp), &mut state, b"\x04\x00\x02\x00\x01\x00\x03\x00\x04", &mut destination), Err((Some(OOB::Reject), &b"\x00\x03\x00\x04"[..])));
+    }
+
+    #[test]
+    fn test_darray_unique() {
+        type Format = DArray<Byte, U16<{ Endianness::Big }>, 4>;
+        assert_eq!(parse_all::<Format, _>(&DArrayUnique(DefaultInterp), [&b"\x03\x00\x01"[..], &b"\x00\x02\x01\x00"[..]]), Ok([1, 2, 0x100].iter().copied().collect::<ArrayVec<u16, 4>>()));
+        assert_eq!(parse_all::<Format, _>(&DArrayUnique(DefaultInterp), [&b"\x03\x00\x01\x00\x01\x00\x02"[..]]), Err(OOB::Duplicate));
+        assert_eq!(parse_all::<Format, _>(&DArrayUnique(DefaultInterp), [&b"\x03\x00\x02\x00\x01\x00\x03"[..]]), Err(OOB::Reject));
+
+        // Rejects at the first duplicate, without reading further elements.
+        let mut state = <DArrayUnique<DefaultInterp> as ParserCommon<Format>>::init(&DArrayUnique(DefaultInterp));
+        let mut destination = None;
+        assert_eq!(<DArrayUnique<DefaultInterp> as InterpParser<Format>>::parse(&DArrayUnique(DefaultInterp), &mut state, b"\x03\x00\x05\x00\x05\x00\x06", &mut destination), Err((Some(OOB::Duplicate), &b"\x00\x06"[..])));
+    }
+
+    #[test]
+    fn test_darray_optional() {
+        type Format = DArray<Byte, U16<{ Endianness::Big }>, 1>;
+        assert_eq!(parse_all::<Format, _>(&DArrayOptional(DefaultInterp), [&b"\x00"[..]]), Ok(None));
+        assert_eq!(parse_all::<Format, _>(&DArrayOptional(DefaultInterp), [&b"\x01\x01"[..], &b"\x02"[..]]), Ok(Some(ArrayVec::from([0x102]))));
+        assert_eq!(parse_all::<Format, _>(&DArrayOptional(DefaultInterp), [&b"\x02\x01\x02\x03\x04"[..]]), Err(OOB::TooManyElements));
+        assert_eq!(parse_all::<Format, _>(&DArrayOptional(DefaultInterp), [&b"\x01\x01"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<DArray<U16<{ Endianness::Big }>, Byte, 3>, _>(&DArrayOptional(DefaultInterp), [&b"\x00\x02ab"[..]]), Ok(Some(b"ab".iter().copied().collect())));
+    }
+
+    #[test]
+    fn test_budgeted() {
+        assert_eq!(parse_all::<Array<Byte, 3>, _>(&Budgeted(4, DefaultInterp), [&b"ab"[..], &b"c"[..]]), Ok(Ok(*b"abc")));
+        assert_eq!(parse_all::<Array<Byte, 3>, _>(&Budgeted(3, DefaultInterp), [&b"abc"[..]]), Ok(Ok(*b"abc")));
+        assert_eq!(parse_all::<Array<Byte, 4>, _>(&Budgeted(3, DefaultInterp), [&b"ab"[..], &b"c"[..]]), Ok(Err(BudgetExceeded)));
+        assert_eq!(parse_all::<Array<Byte, 1>, _>(&Budgeted(0, DefaultInterp), [&b""[..]]), Ok(Err(BudgetExceeded)));
+        assert_eq!(parse_all::<Bcd<2, { BcdPadding::Ignore }>, _>(&Budgeted(3, DefaultInterp), [&b"\x1a"[..]]), Err(OOB::Reject));
+
+        // Running out of budget leaves the rest of the value for the caller.
+        let p = Budgeted(2, DefaultInterp);
+        let mut state = <Budgeted<DefaultInterp> as ParserCommon<Array<Byte, 4>>>::init(&p);
+        let mut destination = None;
+        assert_eq!(<Budgeted<DefaultInterp> as InterpParser<Array<Byte, 4>>>::parse(&p, &mut state, b"abcd", &mut destination), Ok(&b"cd"[..]));
+        assert_eq!(destination, Some(Err(BudgetExceeded)));
+    }
+
+    #[test]
+    fn test_state_size() {
+        assert_eq!(<LengthLimited<DefaultInterp> as ParserCommon<Byte>>::STATE_SIZE, core::mem::size_of::<usize>());
+        assert_eq!(<ByteSwapped<DefaultInterp> as ParserCommon<U32<{ Endianness::Big }>>>::STATE_SIZE, core::mem::size_of::<ArrayVec<u8, 8>>());
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let p = DefaultInterp;
+        let mut state = <DefaultInterp as ParserCommon<Array<Byte, 2>>>::init(&p);
+        let mut destination = None;
+        assert_eq!(ParseStatus::from(<DefaultInterp as InterpParser<Array<Byte, 2>>>::parse(&p, &mut state, b"a", &mut destination)), ParseStatus::NeedMore(&b""[..]));
+        assert_eq!(ParseStatus::from(<DefaultInterp as InterpParser<Array<Byte, 2>>>::parse(&p, &mut state, b"bc", &mut destination)), ParseStatus::Done(&b"c"[..]));
+        let mut state = <DefaultInterp as ParserCommon<Bcd<2, { BcdPadding::Ignore }>>>::init(&p);
+        assert_eq!(ParseStatus::from(<DefaultInterp as InterpParser<Bcd<2, { BcdPadding::Ignore }>>>::parse(&p, &mut state, b"\x1a", &mut None)), ParseStatus::Reject(OOB::Reject, &b"\x1a"[..]));
+
+        assert_eq!(ParseResult::from(ParseStatus::Done(&b"x"[..])), Ok(&b"x"[..]));
+        assert_eq!(ParseResult::from(ParseStatus::NeedMore(&b""[..])), Err((None, &b""[..])));
+        assert_eq!(ParseResult::from(ParseStatus::Reject(OOB::Duplicate, &b"y"[..])), Err((Some(OOB::Duplicate), &b"y"[..])));
+    }
+
+    #[test]
+    fn test_tee() {
+        let p = Tee(DefaultInterp, CaptureRaw::<_, 4>(SubInterp(DropInterp)));
+        assert_eq!(parse_all::<Array<Byte, 3>, _>(&p, [&b"a"[..], &b"bc"[..]]), Ok((*b"abc", ([(); 3], b"abc".iter().copied().collect()))));
+        assert_eq!(parse_all::<Array<Byte, 3>, _>(&p, [&b"ab"[..]]), Err(OOB::Reject));
+
+        // Both accept partway through a chunk.
+        let mut state = <_ as ParserCommon<Array<Byte, 3>>>::init(&p);
+        let mut destination = None;
+        assert_eq!(<_ as InterpParser<Array<Byte, 3>>>::parse(&p, &mut state, b"ab", &mut destination), Err((None, &b""[..])));
+        assert_eq!(<_ as InterpParser<Array<Byte, 3>>>::parse(&p, &mut state, b"cde", &mut destination), Ok(&b"de"[..]));
+        assert_eq!(destination, Some((*b"abc", ([(); 3], b"abc".iter().copied().collect()))));
+
+        // The two sides disagree on where the value ends.
+        let p = Tee(IgnoreTrailing(DefaultInterp), DefaultInterp);
+        assert_eq!(parse_all::<Array<Byte, 2>, _>(&p, [&b"ab"[..]]), Ok((*b"ab", *b"ab")));
+        let mut state = <_ as ParserCommon<Array<Byte, 2>>>::init(&p);
+        let mut destination = None;
+        assert_eq!(<_ as InterpParser<Array<Byte, 2>>>::parse(&p, &mut state, b"a", &mut destination), Err((None, &b""[..])));
+        assert_eq!(<_ as InterpParser<Array<Byte, 2>>>::parse(&p, &mut state, b"bc", &mut destination), Err((Some(OOB::Reject), &b""[..])));
+        // One side accepts while the other still wants more.
+        let p = Tee(Budgeted(1, DefaultInterp), DefaultInterp);
+        let mut state = <_ as ParserCommon<Array<Byte, 2>>>::init(&p);
+        assert_eq!(<_ as InterpParser<Array<Byte, 2>>>::parse(&p, &mut state, b"a", &mut None), Err((Some(OOB::Reject), &b""[..])));
+    }
+
+    #[test]
+    fn test_spanned() {
+        type Format = (Byte, Array<Byte, 3>);
+        let p = Spanned((DefaultInterp, DefaultInterp));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"ab"[..], &b"cd"[..]]), Ok(((Some(b'a'), Some(*b"bcd")), 0..4)));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"abc"[..]]), Err(OOB::Reject));
+
+        // Without a base, each span counts from the start of its own field.
+        let p = (Spanned(DefaultInterp), Spanned(DefaultInterp));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"abcd"[..]]), Ok((Some((b'a', 0..1)), Some((*b"bcd", 0..3)))));
+
+        // With a base, the span is relative to wherever the base was counted from.
+        let p = Spanned(DefaultInterp);
+        let mut state = <_ as ParserCommon<Array<Byte, 3>>>::init(&p);
+        let mut destination = None;
+        <_ as DynParser<Array<Byte, 3>>>::init_param(&p, 1, &mut state, &mut destination);
+        assert_eq!(<_ as InterpParser<Array<Byte, 3>>>::parse(&p, &mut state, b"b", &mut destination), Err((None, &b""[..])));
+        assert_eq!(<_ as InterpParser<Array<Byte, 3>>>::parse(&p, &mut state, b"cde", &mut destination), Ok(&b"e"[..]));
+        assert_eq!(destination, Some((*b"bcd", 1..4)));
+    }
+
+    #[test]
+    fn test_byte_swapped() {
+        let p = ByteSwapped(DefaultInterp);
+        assert_eq!(parse_all::<U32<{ Endianness::Big }>, _>(&p, [&b"\x04\x03"[..], &b"\x02\x01"[..]]), Ok(0x01020304));
+        assert_eq!(parse_all::<U24<{ Endianness::Big }>, _>(&p, [&b"\x03\x02\x01"[..]]), Ok(0x010203));
+        assert_eq!(parse_all::<U64<{ Endianness::Little }>, _>(&p, [&b"\x01\x02\x03\x04\x05\x06\x07\x08"[..]]), Ok(0x0102030405060708));
+
+        let mut state = <_ as ParserCommon<U32<{ Endianness::Big }>>>::init(&p);
+        let mut destination = None;
+        assert_eq!(<_ as InterpParser<U32<{ Endianness::Big }>>>::parse(&p, &mut state, b"\x04\x03\x02\x01\x00", &mut destination), Ok(&b"\x00"[..]));
+        assert_eq!(destination, Some(0x01020304));
+    }
+
+    #[test]
+    fn test_one_of_tags() {
+        let p = OneOfTags([&b"GET "[..], &b"GETX"[..], &b"POST"[..], &b"PUT "[..]]);
+        assert_eq!(parse_all::<Tags, _>(&p, [&b"GE"[..], &b"T "[..]]), Ok(0));
+        assert_eq!(parse_all::<Tags, _>(&p, [&b"GETX"[..]]), Ok(1));
+        assert_eq!(parse_all::<Tags, _>(&p, [&b"P"[..], &b"U"[..], &b"T "[..]]), Ok(3));
+        assert_eq!(parse_all::<Tags, _>(&p, [&b"PU"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<Tags, _>(&OneOfTags::<0>([]), [&b"x"[..]]), Err(OOB::Reject));
+
+        // Rejects at the first byte where every candidate has diverged.
+        let mut state = <OneOfTags<4> as ParserCommon<Tags>>::init(&p);
+        let mut destination = None;
+        assert_eq!(<OneOfTags<4> as InterpParser<Tags>>::parse(&p, &mut state, b"PUSH", &mut destination), Err((Some(OOB::Reject), &b"SH"[..])));
+
+        // The shorter of two overlapping candidates wins, leaving the rest.
+        let p = OneOfTags([&b"ab"[..], &b"a"[..]]);
+        let mut state = <OneOfTags<2> as ParserCommon<Tags>>::init(&p);
+        assert_eq!(<OneOfTags<2> as InterpParser<Tags>>::parse(&p, &mut state, b"abc", &mut destination), Ok(&b"bc"[..]));
+        assert_eq!(destination, Some(1));
+    }
+
+    #[test]
+    fn test_flag_fields() {
+        type Format = Flagged<(Byte, U16<{ Endianness::Big }>, Array<Byte, 2>)>;
+        let p = FlagFields(((0, DefaultInterp), (1, DefaultInterp), (7, DefaultInterp)));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x83\x01\x02"[..], &b"\x03ab"[..]]), Ok((Some(1), Some(0x203), Some(*b"ab"))));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x82\x02\x03ab"[..]]), Ok((None, Some(0x203), Some(*b"ab"))));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x01\x01"[..]]), Ok((Some(1), None, None)));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x00"[..]]), Ok((None, None, None)));
+        // Bit 2 belongs to no field.
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x04"[..]]), Err(OOB::Reject));
+        // A present field that is cut short.
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x02\x01"[..]]), Err(OOB::Reject));
+
+        // Fields are read in tuple order, whatever the order of their bits.
+        let p = FlagFields(((3, DefaultInterp), (0, DefaultInterp)));
+        assert_eq!(parse_all::<Flagged<(Byte, U16<{ Endianness::Big }>)>, _>(&p, [&b"\x09\x01\x02\x03"[..]]), Ok((Some(1), Some(0x203))));
+    }
+
+    #[test]
+    #[should_panic(expected = "bit index out of range")]
+    fn test_flag_fields_bit_out_of_range() {
+        let p = FlagFields(((0, DefaultInterp), (8, DefaultInterp)));
+        let _ = parse_all::<Flagged<(Byte, Byte)>, _>(&p, [&b"\x01\x01"[..]]);
+    }
+
+    #[test]
+    fn test_capture_raw() {
+        type Format = (U16<{ Endianness::Big }>, Byte);
+        let p = CaptureRaw::<_, 3>((DefaultInterp, DefaultInterp));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x01"[..], &b"\x02\x03"[..]]), Ok(((Some(0x102), Some(3)), b"\x01\x02\x03".iter().copied().collect())));
+        assert_eq!(parse_all::<Format, _>(&CaptureRaw::<_, 2>((DefaultInterp, DefaultInterp)), [&b"\x01\x02\x03"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<Bcd<2, { BcdPadding::Ignore }>, _>(&CaptureRaw::<_, 3>(DefaultInterp), [&b"\x1a"[..]]), Err(OOB::Reject));
+
+        // Only the bytes S consumed are captured.
+        let mut state = <_ as ParserCommon<Format>>::init(&p);
+        let mut destination = None;
+        assert_eq!(<_ as InterpParser<Format>>::parse(&p, &mut state, b"abcd", &mut destination), Ok(&b"d"[..]));
+        assert_eq!(destination.map(|d| d.1), Some(b"abc".iter().copied().collect()));
+    }
+
+    #[test]
+    fn test_ignore_trailing() {
+        assert_eq!(parse_all::<Array<Byte, 2>, _>(&IgnoreTrailing(DefaultInterp), [&b"abcd"[..]]), Ok(*b"ab"));
+        assert_eq!(parse_all::<Array<Byte, 2>, _>(&IgnoreTrailing(DefaultInterp), [&b"a"[..], &b"bc"[..]]), Ok(*b"ab"));
+        assert_eq!(parse_all::<Array<Byte, 2>, _>(&IgnoreTrailing(DefaultInterp), [&b"ab"[..]]), Ok(*b"ab"));
+        assert_eq!(parse_all::<Array<Byte, 2>, _>(&IgnoreTrailing(DefaultInterp), [&b"a"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<Bcd<2, { BcdPadding::Ignore }>, _>(&IgnoreTrailing(DefaultInterp), [&b"\x1a\x00"[..]]), Err(OOB::Reject));
+    }
+
+    #[test]
+    fn test_strict_lengthed() {
+        type Format = LengthFallback<Byte, Array<Byte, 3>>;
+        assert_eq!(parse_all::<Format, _>(&StrictLengthed(DefaultInterp), [&b"\x03a"[..], &b"bc"[..]]), Ok(*b"abc"));
+        assert_eq!(parse_all::<Format, _>(&StrictLengthed(DefaultInterp), [&b"\x02ab"[..], &b"c"[..]]), Err(OOB::LengthMismatch));
+        assert_eq!(parse_all::<Format, _>(&StrictLengthed(DefaultInterp), [&b"\x04abcd"[..]]), Err(OOB::LengthMismatch));
+        assert_eq!(parse_all::<LengthFallback<Byte, Bcd<2, { BcdPadding::Ignore }>>, _>(&StrictLengthed(DefaultInterp), [&b"\x01\x1a"[..]]), Err(OOB::Reject));
+
+        // A short value is caught as soon as the subparser accepts, without reading the rest.
+        let p = StrictLengthed(DefaultInterp);
+        let mut state = <StrictLengthed<DefaultInterp> as ParserCommon<Format>>::init(&p);
+        let mut destination = None;
+        assert_eq!(<StrictLengthed<DefaultInterp> as InterpParser<Format>>::parse(&p, &mut state, b"\x05abcde", &mut destination), Err((Some(OOB::LengthMismatch), &b"de"[..])));
+    }
+
+    #[test]
+    fn test_signed_envelope() {
+        type Format = LengthFallback<Byte, (U16<{ Endianness::Big }>, Array<Byte, 2>)>;
+        let p = SignedEnvelope(Crc32::new, Crc32::update, (DefaultInterp, DefaultInterp));
+        let mut crc = Crc32::new();
+        crc.update(b"\x01\x02ab");
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x04\x01"[..], &b"\x02a"[..], &b"b"[..]]).map(|(v, c)| (v, c.finish())), Ok(((Some(0x102), Some(*b"ab")), crc.finish())));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x05\x01\x02abc"[..]]).map(|(v, c)| (v, c.finish())), Err(OOB::LengthMismatch));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x03\x01\x02a"[..]]).map(|(v, c)| (v, c.finish())), Err(OOB::LengthMismatch));
+
+        // A verbatim copy of the value, and nothing after it.
+        let p = SignedEnvelope(|| 0usize, |n: &mut usize, b: &[u8]| { *n += b.len(); }, CaptureRaw::<_, 4>((DefaultInterp, DefaultInterp)));
+        let mut state = <_ as ParserCommon<Format>>::init(&p);
+        let mut destination = None;
+        assert_eq!(<_ as InterpParser<Format>>::parse(&p, &mut state, b"\x04\x01\x02", &mut destination), Err((None, &b""[..])));
+        assert_eq!(<_ as InterpParser<Format>>::parse(&p, &mut state, b"abcd", &mut destination), Ok(&b"cd"[..]));
+        assert_eq!(destination, Some((((Some(0x102), Some(*b"ab")), b"\x01\x02ab".iter().copied().collect()), 4)));
+    }
+
+    #[test]
+    fn test_expect_length() {
+        type Format = LengthFallback<U16<{ Endianness::Big }>, Array<Byte, 4>>;
+        assert_eq!(parse_all::<Format, _>(&ExpectLength::<4>, [&b"\x00"[..], &b"\x04ab"[..], &b"cd"[..]]), Ok(*b"abcd"));
+        assert_eq!(parse_all::<Format, _>(&ExpectLength::<4>, [&b"\x00\x04abc"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<Format, _>(&ExpectLength::<4>, [&b"\x00\x03abc"[..]]), Err(OOB::LengthMismatch));
+        assert_eq!(parse_all::<Format, _>(&ExpectLength::<4>, [&b"\x00\x05abcde"[..]]), Err(OOB::LengthMismatch));
+
+        // The rejection points at the length field, before any of the value is read.
+        let mut state = <ExpectLength<4> as ParserCommon<Format>>::init(&ExpectLength);
+        let mut destination = None;
+        assert_eq!(<ExpectLength<4> as InterpParser<Format>>::parse(&ExpectLength, &mut state, b"\x00\x20abcd", &mut destination), Err((Some(OOB::LengthMismatch), &b"\x00\x20abcd"[..])));
+    }
+
+    #[test]
+    fn test_observe_lengthed_bytes() {
+        type Format = LengthFallback<Byte, Array<Byte, 3>>;
+        let p = ObserveLengthedBytes(|| 0usize, |n: &mut usize, b: &[u8]| { *n += b.len(); }, DefaultInterp, false);
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x03ab"[..], &b"c"[..]]), Ok((Some(*b"abc"), 3, Lengthed::Parsed)));
+        // Too long for the subparser, so it accepts early and the rest is skipped.
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x05abc"[..], &b"de"[..]]), Ok((None, 5, Lengthed::Skipped)));
+        // Too short, so the subparser never gets to accept.
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x02ab"[..]]), Ok((None, 2, Lengthed::Skipped)));
+        // Rejected by the subparser.
+        assert_eq!(parse_all::<LengthFallback<Byte, Bcd<2, { BcdPadding::Ignore }>>, _>(&p, [&b"\x02\x1a\x00"[..]]), Ok((None, 2, Lengthed::Skipped)));
+    }
+
+    #[test]
+    fn test_u24_length_fallback() {
+        // A three-byte length, split across chunks both inside the prefix and inside the value.
+        type Format = LengthFallback<U24<{ Endianness::Big }>, Array<Byte, 3>>;
+        let p = ObserveLengthedBytes(|| 0usize, |n: &mut usize, b: &[u8]| { *n += b.len(); }, DefaultInterp, false);
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x00"[..], &b"\x00\x03a"[..], &b"bc"[..]]), Ok((Some(*b"abc"), 3, Lengthed::Parsed)));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x00\x00"[..], &b"\x05abc"[..], &b"de"[..]]), Ok((None, 5, Lengthed::Skipped)));
+        assert_eq!(parse_all::<Format, _>(&StrictLengthed(DefaultInterp), [&b"\x00\x00"[..], &b"\x03ab"[..], &b"c"[..]]), Ok(*b"abc"));
+        assert_eq!(parse_all::<LengthFallback<U24<{ Endianness::Little }>, Array<Byte, 3>>, _>(&StrictLengthed(DefaultInterp), [&b"\x03"[..], &b"\x00\x00abc"[..]]), Ok(*b"abc"));
+    }
+
+    #[test]
+    fn test_byte_dispatch() {
+        type Format = Dispatch<(Byte, U16<{ Endianness::Big }>, Array<Byte, 0>)>;
+        let p = ByteDispatch(|tag| match tag {
+            0x10..=0x1f => Some(0),
+            0x20 => Some(1),
+            0x30 => Some(2),
+            0x40 => Some(3),
+            _ => None,
+        }, (DefaultInterp, DefaultInterp, DefaultInterp));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x12a"[..]]), Ok(Dispatched3::Branch0(b'a')));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x20"[..], &b"\x01"[..], &b"\x02"[..]]), Ok(Dispatched3::Branch1(0x0102)));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x30"[..]]), Ok(Dispatched3::Branch2([])));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x40"[..]]), Err(OOB::Reject));
+
+        let mut state = <ByteDispatch<(DefaultInterp, DefaultInterp, DefaultInterp)> as ParserCommon<Format>>::init(&p);
+        let mut destination = None;
+        assert_eq!(<ByteDispatch<(DefaultInterp, DefaultInterp, DefaultInterp)> as InterpParser<Format>>::parse(&p, &mut state, b"\x00a", &mut destination), Err((Some(OOB::Reject), &b"\x00a"[..])));
+    }
+
+    #[test]
+    fn test_big_uint_bytes() {
+        use arrayvec::ArrayString;
+        use core::fmt::Write;
+        let max = parse_all::<Array<Byte, 32>, _>(&BigUintInterp, [&[0xff; 20][..], &[0xff; 12][..]]).unwrap();
+        let mut s = ArrayString::<80>::new();
+        write!(s, "{}", max).unwrap();
+        assert_eq!(s.as_str(), "115792089237316195423570985008687907853269984665640564039457584007913129639935");
+        s.clear();
+        write!(s, "{:x}", max).unwrap();
+        assert_eq!(s.as_str(), "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff");
+
+        let mut bytes = [0; 20];
+        bytes[12..].copy_from_slice(&1_000_000_000_000_000_000u64.to_be_bytes());
+        let one_ether = parse_all::<Array<Byte, 20>, _>(&BigUintInterp, [&bytes[..]]).unwrap();
+        s.clear();
+        write!(s, "{} {:x}", one_ether, one_ether).unwrap();
+        assert_eq!(s.as_str(), "1000000000000000000 de0b6b3a7640000");
+        assert!(one_ether < BigUintBytes([0xff; 20]));
+        assert!(one_ether > BigUintBytes([0; 20]));
+
+        s.clear();
+        write!(s, "{} {:x}", BigUintBytes([0; 4]), BigUintBytes([0; 4])).unwrap();
+        assert_eq!(s.as_str(), "0 0");
+        // Zeros inside a group of nine digits are kept.
+        s.clear();
+        write!(s, "{}", BigUintBytes(1_000_000_007u64.to_be_bytes())).unwrap();
+        assert_eq!(s.as_str(), "1000000007");
+    }
+
+    #[test]
+    fn test_uleb128() {
+        assert_eq!(parse_all::<ULEB128<u32, false>, _>(&DefaultInterp, [&b"\x00"[..]]), Ok(0));
+        assert_eq!(parse_all::<ULEB128<u32, false>, _>(&DefaultInterp, [&b"\xac"[..], &b"\x02"[..]]), Ok(300));
+        assert_eq!(parse_all::<ULEB128<u64, true>, _>(&DefaultInterp, [&b"\xff\xff\xff\xff\xff\xff\xff\xff\xff\x01"[..]]), Ok(u64::MAX));
+        assert_eq!(parse_all::<ULEB128<u32, true>, _>(&DefaultInterp, [&b"\xff\xff\xff\xff\x0f"[..]]), Ok(u32::MAX));
+        assert_eq!(parse_all::<ULEB128<u32, true>, _>(&DefaultInterp, [&b"\xff\xff\xff\xff\x1f"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<ULEB128<u16, false>, _>(&DefaultInterp, [&b"\x80\x80\x80\x00"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<ULEB128<u32, false>, _>(&DropInterp, [&b"\xac\x02"[..]]), Ok(()));
+
+        // Overlong encodings are only accepted in lax mode.
+        assert_eq!(parse_all::<ULEB128<u32, false>, _>(&DefaultInterp, [&b"\x81\x00"[..]]), Ok(1));
+        assert_eq!(parse_all::<ULEB128<u32, true>, _>(&DefaultInterp, [&b"\x81\x00"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<ULEB128<u32, true>, _>(&DefaultInterp, [&b"\x80\x00"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<ULEB128<u32, true>, _>(&DefaultInterp, [&b"\x80\x01"[..]]), Ok(128));
+    }
+
+    #[test]
+    fn test_zigzag() {
+        assert_eq!(parse_all::<ZigZag<i64, true>, _>(&DefaultInterp, [&b"\x00"[..]]), Ok(0));
+        assert_eq!(parse_all::<ZigZag<i64, true>, _>(&DefaultInterp, [&b"\x01"[..]]), Ok(-1));
+        assert_eq!(parse_all::<ZigZag<i64, true>, _>(&DefaultInterp, [&b"\x02"[..]]), Ok(1));
+        assert_eq!(parse_all::<ZigZag<i64, true>, _>(&DefaultInterp, [&b"\xff\xff\xff\xff"[..], &b"\xff\xff\xff\xff\xff\x01"[..]]), Ok(i64::MIN));
+        assert_eq!(parse_all::<ZigZag<i64, true>, _>(&DefaultInterp, [&b"\xfe\xff\xff\xff\xff\xff\xff\xff\xff\x01"[..]]), Ok(i64::MAX));
+        assert_eq!(parse_all::<ZigZag<i32, true>, _>(&DefaultInterp, [&b"\xff\xff\xff\xff\x0f"[..]]), Ok(i32::MIN));
+        assert_eq!(parse_all::<ZigZag<i32, true>, _>(&DefaultInterp, [&b"\xff\xff\xff\xff\x1f"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<ZigZag<i32, true>, _>(&DefaultInterp, [&b"\x81\x00"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<ZigZag<i32, false>, _>(&DefaultInterp, [&b"\x81\x00"[..]]), Ok(-1));
+        assert_eq!(parse_all::<ZigZag<i64, true>, _>(&DropInterp, [&b"\x01"[..]]), Ok(()));
+    }
+
+    #[test]
+    fn test_fixed_groups() {
+        assert_eq!(parse_all::<FixedGroups<u32, 2, true>, _>(&DefaultInterp, [&b"\x2c"[..], &b"\x02"[..]]), Ok(300));
+        assert_eq!(parse_all::<FixedGroups<u32, 2, true>, _>(&DefaultInterp, [&b"\x00\x00"[..]]), Ok(0));
+        assert_eq!(parse_all::<FixedGroups<u32, 2, true>, _>(&DefaultInterp, [&b"\x2c"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<FixedGroups<u16, 3, true>, _>(&DefaultInterp, [&b"\x7f\x7f\x03"[..]]), Ok(u16::MAX));
+        assert_eq!(parse_all::<FixedGroups<u16, 3, true>, _>(&DefaultInterp, [&b"\x7f\x7f\x04"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<FixedGroups<u64, 10, true>, _>(&DefaultInterp, [&b"\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x01"[..]]), Ok(u64::MAX));
+        assert_eq!(parse_all::<FixedGroups<u32, 2, true>, _>(&DropInterp, [&b"\x2c\x02"[..]]), Ok(()));
+
+        // The high bit is rejected in strict mode and ignored otherwise.
+        assert_eq!(parse_all::<FixedGroups<u32, 2, true>, _>(&DefaultInterp, [&b"\xac\x02"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<FixedGroups<u32, 2, true>, _>(&DefaultInterp, [&b"\x2c\x82"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<FixedGroups<u32, 2, false>, _>(&DefaultInterp, [&b"\xac\x82"[..]]), Ok(300));
+        assert_eq!(parse_all::<FixedGroups<u32, 2, false>, _>(&DefaultInterp, [&b"\x80\x80"[..]]), Ok(0));
+
+        // Exactly GROUPS bytes are read, whatever the high bits say.
+        let mut state = <DefaultInterp as ParserCommon<FixedGroups<u32, 2, false>>>::init(&DefaultInterp);
+        let mut destination = None;
+        assert_eq!(<DefaultInterp as InterpParser<FixedGroups<u32, 2, false>>>::parse(&DefaultInterp, &mut state, b"\x01\x01\x01", &mut destination), Ok(&b"\x01"[..]));
+        assert_eq!(destination, Some(129));
+    }
+
+    #[test]
+    fn test_wide_varints() {
+        assert_eq!(parse_all::<ULEB128<u128, true>, _>(&DefaultInterp, [&b"\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\x03"[..]]), Ok(u128::MAX));
+        assert_eq!(parse_all::<ULEB128<u128, true>, _>(&DefaultInterp, [&b"\xfe\xff\xff\xff\xff\xff\xff\xff\xff"[..], &b"\xff\xff\xff\xff\xff\xff\xff\xff\xff\x03"[..]]), Ok(u128::MAX - 1));
+        assert_eq!(parse_all::<ULEB128<u128, true>, _>(&DefaultInterp, [&b"\x80\x80\x80\x80\x80\x80\x80\x80\x80\x80\x80\x80\x80\x80\x80\x80\x80\x80\x02"[..]]), Ok(1 << 127));
+        // One bit past the top, and a group entirely past it.
+        assert_eq!(parse_all::<ULEB128<u128, true>, _>(&DefaultInterp, [&b"\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\x07"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<ULEB128<u128, false>, _>(&DefaultInterp, [&b"\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\x83\x00"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<ULEB128<u128, false>, _>(&DropInterp, [&b"\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\x03"[..]]), Ok(()));
+
+        assert_eq!(parse_all::<FixedGroups<u128, 19, true>, _>(&DefaultInterp, [&b"\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x03"[..]]), Ok(u128::MAX));
+        assert_eq!(parse_all::<FixedGroups<u128, 19, true>, _>(&DefaultInterp, [&b"\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x04"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<FixedGroups<u128, 20, true>, _>(&DefaultInterp, [&b"\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x03\x00"[..]]), Ok(u128::MAX));
+        assert_eq!(parse_all::<FixedGroups<u128, 20, true>, _>(&DefaultInterp, [&b"\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x7f\x03\x01"[..]]), Err(OOB::Reject));
+    }
+
+    #[test]
+    fn test_with_footer() {
+        type Format = (DArray<Byte, Byte, 4>, Array<Byte, 2>);
+        let p = WithFooter(SubInterp(DefaultInterp), *b"\r\n");
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x02ab\r"[..], &b"\n"[..]]), Ok(b"ab".iter().copied().collect()));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x00\r\n"[..]]), Ok(ArrayVec::new()));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x02ab\r"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"\x02ab\n\r"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<(Byte, Array<Byte, 0>), _>(&WithFooter(DefaultInterp, []), [&b"a"[..]]), Ok(b'a'));
+
+        // Rejects at the first wrong footer byte, even in a later chunk.
+        let mut state = <_ as ParserCommon<Format>>::init(&p);
+        let mut destination = None;
+        assert_eq!(<_ as InterpParser<Format>>::parse(&p, &mut state, b"\x01a\r", &mut destination), Err((None, &b""[..])));
+        assert_eq!(<_ as InterpParser<Format>>::parse(&p, &mut state, b"\r\n", &mut destination), Err((Some(OOB::Reject), &b"\r\n"[..])));
+    }
+
+    #[test]
+    fn test_checksummed() {
+        let mut crc = Crc32::new();
+        crc.update(b"12345");
+        crc.update(b"6789");
+        assert_eq!(crc.finish(), 0xcbf43926);
+
+        type Format = (Array<Byte, 9>, U32<{ Endianness::Little }>);
+        let p = Checksummed(Crc32::new, Crc32::update, Crc32::finish, DefaultInterp);
+        assert_eq!(parse_all::<Format, _>(&p, [&b"1234"[..], &b"56789\x26"[..], &b"\x39\xf4\xcb"[..]]), Ok(*b"123456789"));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"123456789\x26\x39\xf4\xcc"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<Format, _>(&p, [&b"123456780\x26\x39\xf4\xcb"[..]]), Err(OOB::Reject));
+    }
+
+    sum_type! { enum Instr : U16<{ Endianness::Big }> {
+        Push(0) : U32<{ Endianness::Big }>,
+        Pop(1) : Array<Byte, 0>,
+        Dup(2 | 3) : Byte,
+    } }
+
+    #[test]
+    fn test_sum_type() {
+        assert_eq!(parse_all::<InstrSchema, _>(&DefaultInterp, [&b"\x00\x00\x01"[..], &b"\x02\x03\x04"[..]]), Ok(Instr::Push(0x01020304)));
+        assert_eq!(parse_all::<InstrSchema, _>(&DefaultInterp, [&b"\x00"[..], &b"\x01"[..]]), Ok(Instr::Pop([])));
+        assert_eq!(parse_all::<InstrSchema, _>(&DefaultInterp, [&b"\x00\x03\x07"[..]]), Ok(Instr::Dup(7)));
+        assert_eq!(parse_all::<InstrSchema, _>(&DefaultInterp, [&b"\x00\x04\x07"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<InstrSchema, _>(&DefaultInterp, [&b"\x00\x00\x01\x02"[..]]), Err(OOB::Reject));
+    }
+
+    #[test]
+    fn test_der_length() {
+        assert_eq!(parse_all::<DerLength, _>(&DefaultInterp, [&b"\x00"[..]]), Ok(0));
+        assert_eq!(parse_all::<DerLength, _>(&DefaultInterp, [&b"\x7f"[..]]), Ok(0x7f));
+        assert_eq!(parse_all::<DerLength, _>(&DefaultInterp, [&b"\x81\x80"[..]]), Ok(0x80));
+        assert_eq!(parse_all::<DerLength, _>(&DefaultInterp, [&b"\x81"[..], &b"\xff"[..]]), Ok(0xff));
+        assert_eq!(parse_all::<DerLength, _>(&DefaultInterp, [&b"\x82\x01"[..], &b"\x00"[..]]), Ok(0x100));
+        assert_eq!(parse_all::<DerLength, _>(&DefaultInterp, [&b"\x82\xff\xff"[..]]), Ok(0xffff));
+
+        // Indefinite and reserved forms.
+        assert_eq!(parse_all::<DerLength, _>(&DefaultInterp, [&b"\x80"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<DerLength, _>(&DefaultInterp, [&b"\xff"[..]]), Err(OOB::Reject));
+        // Non-minimal encodings.
+        assert_eq!(parse_all::<DerLength, _>(&DefaultInterp, [&b"\x81\x7f"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<DerLength, _>(&DefaultInterp, [&b"\x82\x00\x80"[..]]), Err(OOB::Reject));
+        // Too long for usize.
+        assert_eq!(parse_all::<DerLength, _>(&DefaultInterp, [&b"\x89\x01\x00\x00\x00\x00\x00\x00\x00\x00"[..]]), Err(OOB::Reject));
+    }
+
+    #[test]
+    fn test_der_tlv() {
+        // An INTEGER holding 0x0102, then a SEQUENCE of two bytes.
+        assert_eq!(parse_all::<Tlv<U16<{ Endianness::Big }>>, _>(&DerTlv(Some(0x02), DefaultInterp), [&b"\x02\x02\x01"[..], &b"\x02"[..]]), Ok((0x02, 0x0102)));
+        assert_eq!(parse_all::<Tlv<DArray<Byte, Byte, 4>>, _>(&DerTlv(None, SubInterp(DefaultInterp)), [&b"\x30\x03\x02ab"[..]]), Ok((0x30, b"ab".iter().copied().collect::<ArrayVec<u8, 4>>())));
+        // A long-form length.
+        let mut long = [0u8; 131];
+        long[..3].copy_from_slice(b"\x04\x81\x80");
+        assert_eq!(parse_all::<Tlv<Array<Byte, 128>>, _>(&DerTlv(Some(0x04), DefaultInterp), [&long[..]]), Ok((0x04, [0; 128])));
+        // Constructed where primitive was expected.
+        assert_eq!(parse_all::<Tlv<Array<Byte, 1>>, _>(&DerTlv(Some(0x04), DefaultInterp), [&b"\x24\x01a"[..]]), Err(OOB::Reject));
+        // The value must fill the length exactly.
+        assert_eq!(parse_all::<Tlv<Array<Byte, 1>>, _>(&DerTlv(None, DefaultInterp), [&b"\x04\x02ab"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<Tlv<Array<Byte, 2>>, _>(&DerTlv(None, DefaultInterp), [&b"\x04\x01ab"[..]]), Err(OOB::Reject));
+    }
+
+    #[test]
+    fn test_array_progress() {
+        type Format = Array<U16<{ Endianness::Big }>, 3>;
+        let mut state = <DefaultInterp as ParserCommon<Format>>::init(&DefaultInterp);
+        let mut destination = None;
+        assert_eq!(state.progress(), 0);
+        assert_eq!(<DefaultInterp as InterpParser<Format>>::parse(&DefaultInterp, &mut state, b"\x00\x01\x00", &mut destination), Err((None, &b""[..])));
+        assert_eq!(state.progress(), 1);
+        assert_eq!(<DefaultInterp as InterpParser<Format>>::parse(&DefaultInterp, &mut state, b"\x02", &mut destination), Err((None, &b""[..])));
+        assert_eq!(state.progress(), 2);
+
+        type DFormat = DArray<Byte, U16<{ Endianness::Big }>, 3>;
+        let mut state = <SubInterp<DefaultInterp> as ParserCommon<DFormat>>::init(&SubInterp(DefaultInterp));
+        let mut destination = None;
+        assert_eq!(state.progress(), None);
+        assert_eq!(<SubInterp<DefaultInterp> as InterpParser<DFormat>>::parse(&SubInterp(DefaultInterp), &mut state, b"\x03\x00\x01\x00", &mut destination), Err((None, &b""[..])));
+        assert_eq!(state.progress(), Some((1, 3)));
+    }
+
+    #[test]
+    fn test_until() {
+        assert_eq!(parse_all::<Until<b'\n', 5, false>, _>(&DefaultInterp, [&b"ab"[..], &b"c"[..], &b"\n"[..]]), Ok(b"abc".iter().copied().collect()));
+        assert_eq!(parse_all::<Until<b'\n', 5, true>, _>(&DefaultInterp, [&b"ab"[..], &b"c\n"[..]]), Ok(b"abc\n".iter().copied().collect()));
+        assert_eq!(parse_all::<Until<0, 3, false>, _>(&DefaultInterp, [&b"\x00"[..]]), Ok(ArrayVec::new()));
+        assert_eq!(parse_all::<Until<0, 3, false>, _>(&DefaultInterp, [&b"abc\x00"[..]]), Ok(ArrayVec::from(*b"abc")));
+        // With KEEP the delimiter needs room as well.
+        assert_eq!(parse_all::<Until<0, 3, true>, _>(&DefaultInterp, [&b"abc\x00"[..]]), Err(OOB::Reject));
+
+        // Only the first delimiter ends the field.
+        let mut destination = None;
+        assert_eq!(<DefaultInterp as InterpParser<Until<b';', 4, false>>>::parse(&DefaultInterp, &mut (), b"ab;cd;", &mut destination), Ok(&b"cd;"[..]));
+        assert_eq!(destination, Some(b"ab".iter().copied().collect()));
+    }
+
+    #[test]
+    fn test_display_string() {
+        type Format = DArray<Byte, Byte, 32>;
+        let show = |chunks: &[&[u8]]| parse_all::<Format, _>(&DisplayString::<32, 8>, chunks.iter().copied());
+        let text = |t: &str| Ok(ArrayString::<8>::from(t).unwrap());
+        assert_eq!(show(&[b"\x05hello"]), text("hello"));
+        assert_eq!(show(&[b"\x08abcdefgh"]), text("abcdefgh"));
+        assert_eq!(show(&[b"\x09abcde", b"fghi"]), text("abcde\u{2026}"));
+        assert_eq!(show(&[b"\x00"]), text(""));
+        // A character split across chunks.
+        assert_eq!(show(&[b"\x05caf\xc3", b"\xa9"]), text("caf\u{e9}"));
+        // A two-byte character that would straddle the cut is left out entirely.
+        assert_eq!(show(&[b"\x0aabcd\xc3\xa9xyzw"]), text("abcd\u{2026}"));
+        assert_eq!(show(&[b"\x0aabc\xc3\xa9vwxyz"]), text("abc\u{e9}\u{2026}"));
+        // After a character that doesn't fit, nothing more is shown even if it would.
+        assert_eq!(show(&[b"\x0aab\xf0\x9f\x98\x80\xf0\x9f\x98\x80"]), text("ab\u{2026}"));
+
+        // Invalid UTF-8 is rejected, even past what is shown.
+        assert_eq!(show(&[b"\x02\xc3("]), Err(OOB::Reject));
+        assert_eq!(show(&[b"\x0aabcdefgh\xff\x80"]), Err(OOB::Reject));
+        assert_eq!(show(&[b"\x03\xed\xa0\x80"]), Err(OOB::Reject));
+        assert_eq!(show(&[b"\x02\xc0\x80"]), Err(OOB::Reject));
+        // The length ends partway through a character.
+        assert_eq!(show(&[b"\x01\xc3\xa9"]), Err(OOB::Reject));
+        assert_eq!(show(&[b"\x21abcdefghijklmnopqrstuvwxyzabcdefg"]), Err(OOB::TooManyElements));
+    }
+
+    #[test]
+    fn test_parse_all_reject() {
+        // Ran out of input.
+        assert_eq!(parse_all::<Array<Byte, 5>, _>(&DefaultInterp, [&b"foo"[..]]), Err(OOB::Reject));
+        // Trailing input.
+        assert_eq!(parse_all::<Array<Byte, 2>, _>(&DefaultInterp, [&b"foo"[..]]), Err(OOB::Reject));
+        assert_eq!(parse_all::<Array<Byte, 3>, _>(&DefaultInterp, [&b"foo"[..], &b"bar"[..]]), Err(OOB::Reject));
+        // Declared length too long for the buffer.
+        assert_eq!(parse_all::<DArray<Byte, Byte, 2>, _>(&SubInterp(DefaultInterp), [&b"\x03abc"[..]]), Err(OOB::TooManyElements));
+    }
+}